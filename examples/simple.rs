@@ -1,7 +1,7 @@
 extern crate codemap_diagnostic;
 extern crate codemap;
 use codemap::{ CodeMap };
-use codemap_diagnostic::{ Level, SpanLabel, SpanStyle, Diagnostic, ColorConfig, Emitter };
+use codemap_diagnostic::{ Level, SpanLabel, SpanStyle, Diagnostic, SubDiagnostic, ColorConfig, Emitter };
 
 
 fn main() {
@@ -21,12 +21,13 @@ pub fn look_up_pos(&self, pos: Pos) -> Loc {
 
     let sl = SpanLabel { span: fn_span, style: SpanStyle::Primary, label:Some("function name".to_owned()) };
     let sl2 = SpanLabel { span: ret_span, style: SpanStyle::Primary, label:Some("returns".to_owned()) };
-    let d1 = Diagnostic { level:Level::Error, message:"Test error".to_owned(), code:Some("C000".to_owned()), spans: vec![sl, sl2] };
+    let note = SubDiagnostic { level: Level::Note, message:"Test note".into(), spans: vec![] };
+    let d1 = Diagnostic { level:Level::Error, message:"Test error".into(), code:Some("C000".to_owned()), spans: vec![sl, sl2], children: vec![note], suggestions: vec![] };
 
     let sl3 = SpanLabel { span: var_span, style: SpanStyle::Primary, label:Some("variable".to_owned()) };
-    let d2 = Diagnostic { level:Level::Warning, message:"Test warning".to_owned(), code:Some("W000".to_owned()), spans: vec![sl3] };
+    let d2 = Diagnostic { level:Level::Warning, message:"Test warning".into(), code:Some("W000".to_owned()), spans: vec![sl3], children: vec![], suggestions: vec![] };
 
-    let d3 = Diagnostic { level: Level::Help, message:"Help message".to_owned(), code: None, spans: vec![] };
+    let d3 = Diagnostic { level: Level::Help, message:"Help message".into(), code: None, spans: vec![], children: vec![], suggestions: vec![] };
 
     let mut emitter = Emitter::stderr(ColorConfig::Auto, Some(&codemap));
     emitter.emit(&[d1, d2, d3]);
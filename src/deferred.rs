@@ -0,0 +1,161 @@
+//! Buffering diagnostics to print them in a deterministic order.
+
+use std::io;
+use codemap::{CodeMap, Pos, Span};
+use { Applicability, Diagnostic, Emit, Level, Message, SpanLabel, SpanStyle };
+
+/// Collects groups of diagnostic messages and prints them sorted by location when flushed.
+///
+/// Groups are ordered by the file name, line and column of the primary span of their first
+/// diagnostic, then by level, code and message, so the output doesn't depend on the order in
+/// which they were emitted. Groups without a primary span come last. Groups that are still
+/// tied are ordered by the rest of their contents, so only identical groups keep their
+/// relative order.
+///
+/// Nothing is printed until `flush` or `finish` is called. Groups still buffered when the
+/// emitter is dropped are flushed then, ignoring any error from writing to the output.
+///
+/// When used with a `Handler`, the deferred emitter must wrap the handler rather than the other
+/// way around, so that the handler's error limit and deduplication see the groups in sorted
+/// order. The handler's counts are complete once the deferred emitter is finished:
+///
+/// ```
+/// # extern crate codemap_diagnostic;
+/// # use codemap_diagnostic::*;
+/// # fn main() {
+/// let emitter = Emitter::stderr(ColorConfig::Auto, None);
+/// let mut handler = Handler::new(Box::new(emitter)).error_limit(Some(10));
+/// {
+///     let mut deferred = DeferredEmitter::new(Box::new(&mut handler), None);
+///     // Emit diagnostics to `deferred` in any order
+///     Emit::finish(&mut deferred).unwrap();
+/// }
+/// if handler.has_errors() {
+///     // Exit with a failure status
+/// }
+/// # }
+/// ```
+pub struct DeferredEmitter<'a> {
+    emitter: Box<dyn Emit + 'a>,
+    cm: Option<&'a CodeMap>,
+    groups: Vec<Vec<Diagnostic>>,
+}
+
+/// The position in the sort order of a group of diagnostics.
+///
+/// The first element is true for groups without a location, to sort them last. It is followed
+/// by the location of the primary span of the first diagnostic, and the contents of each
+/// diagnostic.
+type SortKey = (bool, Option<(String, usize, usize)>, Vec<DiagnosticKey>);
+
+type DiagnosticKey = (Level, Option<String>, String, Message, Vec<SpanKey>, Vec<ChildKey>,
+                      Vec<SuggestionKey>);
+type SpanKey = (Pos, Pos, SpanStyle, Option<String>);
+type ChildKey = (Level, Message, Vec<SpanKey>);
+type SuggestionKey = (Pos, Pos, String, Message, Applicability);
+
+fn span_key(span: Span) -> (Pos, Pos) {
+    (span.low(), span.high())
+}
+
+fn span_label_keys(spans: &[SpanLabel]) -> Vec<SpanKey> {
+    spans.iter().map(|s| {
+        let (low, high) = span_key(s.span);
+        (low, high, s.style, s.label.clone())
+    }).collect()
+}
+
+fn diagnostic_key(msg: &Diagnostic) -> DiagnosticKey {
+    let children = msg.children.iter().map(|child| {
+        (child.level, child.message.clone(), span_label_keys(&child.spans))
+    }).collect();
+    let suggestions = msg.suggestions.iter().map(|s| {
+        let (low, high) = span_key(s.span);
+        (low, high, s.replacement.clone(), s.message.clone(), s.applicability)
+    }).collect();
+    (msg.level, msg.code.clone(), msg.message.to_string(), msg.message.clone(),
+     span_label_keys(&msg.spans), children, suggestions)
+}
+
+impl<'a> DeferredEmitter<'a> {
+    /// Creates a deferred emitter printing to another output format.
+    ///
+    /// `code_map` is used to find the location of the primary spans.
+    pub fn new(emitter: Box<dyn Emit + 'a>, code_map: Option<&'a CodeMap>) -> DeferredEmitter<'a> {
+        DeferredEmitter {
+            emitter,
+            cm: code_map,
+            groups: vec![],
+        }
+    }
+
+    fn sort_key(&self, group: &[Diagnostic]) -> SortKey {
+        let location = group.first().and_then(|msg| {
+            let cm = self.cm?;
+            let primary_span = msg.spans.iter().find(|s| s.style == SpanStyle::Primary)?;
+            let loc = cm.look_up_pos(primary_span.span.low());
+            Some((loc.file.name().to_owned(), loc.position.line, loc.position.column))
+        });
+
+        (location.is_none(), location, group.iter().map(diagnostic_key).collect())
+    }
+
+    /// Add a group of diagnostic messages to be printed when flushed.
+    pub fn emit(&mut self, msgs: &[Diagnostic]) {
+        if !msgs.is_empty() {
+            self.groups.push(msgs.to_vec());
+        }
+    }
+
+    /// Print the buffered diagnostics in sorted order.
+    ///
+    /// # Panics
+    ///
+    /// If writing to the output fails. Use `try_flush` to handle the error instead.
+    pub fn flush(&mut self) {
+        if let Err(e) = self.try_flush() {
+            panic!("failed to emit error: {}", e);
+        }
+    }
+
+    /// Print the buffered diagnostics in sorted order, returning any error from writing to the
+    /// output.
+    pub fn try_flush(&mut self) -> io::Result<()> {
+        let mut groups: Vec<(SortKey, Vec<Diagnostic>)> = ::std::mem::take(&mut self.groups)
+            .into_iter()
+            .map(|group| (self.sort_key(&group), group))
+            .collect();
+        groups.sort_by(|a, b| a.0.cmp(&b.0));
+
+        for (_, group) in groups {
+            self.emitter.emit(&group)?;
+        }
+        self.emitter.flush()
+    }
+}
+
+impl<'a> Emit for DeferredEmitter<'a> {
+    fn emit(&mut self, msgs: &[Diagnostic]) -> io::Result<()> {
+        DeferredEmitter::emit(self, msgs);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.try_flush()
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.emitter.is_human_readable()
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.try_flush()?;
+        self.emitter.finish()
+    }
+}
+
+impl<'a> Drop for DeferredEmitter<'a> {
+    fn drop(&mut self) {
+        drop(self.try_flush());
+    }
+}
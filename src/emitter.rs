@@ -10,16 +10,21 @@
 
 use std::io::prelude::*;
 use std::io;
-use std::cmp::min;
+use std::cmp::{max, min};
 use std::sync::Arc;
 use std::collections::HashMap;
 use termcolor::{StandardStream, ColorChoice, ColorSpec, BufferWriter};
-use termcolor::{WriteColor, Color, Buffer};
+use termcolor::{WriteColor, Buffer};
 use std::io::IsTerminal;
-use { Level, Diagnostic, SpanLabel, SpanStyle };
+use terminal_size::{Width, terminal_size_of};
+use { Level, Diagnostic, Emit, Message, SpanLabel, SpanStyle, Suggestion };
 use codemap::{CodeMap, File};
 use snippet::{Annotation, AnnotationType, Line, MultilineAnnotation, StyledString, Style};
-use styled_buffer::StyledBuffer;
+use styled_buffer::{StyledBuffer, display_col, expand_tabs, slice_columns, split_backticks,
+                    str_width};
+use theme::Theme;
+use highlight::Highlighter;
+use registry::Registry;
 
 /// Settings for terminal styling.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -35,8 +40,8 @@ pub enum ColorConfig {
 }
 
 impl ColorConfig {
-    fn to_color_choice(&self) -> ColorChoice {
-        match *self {
+    fn to_color_choice(self) -> ColorChoice {
+        match self {
             ColorConfig::Always => ColorChoice::Always,
             ColorConfig::Never => ColorChoice::Never,
             ColorConfig::Auto if std::io::stderr().is_terminal() => {
@@ -47,10 +52,133 @@ impl ColorConfig {
     }
 }
 
+/// Character set used to draw the margin, underlines and connecting lines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Charset {
+    /// Draw with ASCII characters such as `|`, `_` and `^`.
+    Ascii,
+
+    /// Draw with Unicode box-drawing characters such as `│`, `─` and `━`.
+    Unicode,
+}
+
+struct Glyphs {
+    /// Prefix of the location of the primary span
+    primary_location: &'static str,
+    /// Prefix of the name of a secondary file
+    secondary_file: &'static str,
+    /// Separator between the line numbers and the code
+    col_separator: char,
+    /// Vertical line of a multiline span, and of a label below its underline
+    vertical: char,
+    /// Horizontal line connecting a multiline span to its start and end columns
+    horizontal: char,
+    /// Start of a multiline span that begins at the start of its first line
+    multiline_start: char,
+    /// Corner at the start of a multiline span, if any
+    multiline_start_corner: Option<char>,
+    /// Corner at the end of a multiline span
+    multiline_end_corner: char,
+    underline_primary: char,
+    underline_secondary: char,
+}
+
+const ASCII_GLYPHS: Glyphs = Glyphs {
+    primary_location: "--> ",
+    secondary_file: "::: ",
+    col_separator: '|',
+    vertical: '|',
+    horizontal: '_',
+    multiline_start: '/',
+    multiline_start_corner: None,
+    multiline_end_corner: '|',
+    underline_primary: '^',
+    underline_secondary: '-',
+};
+
+const UNICODE_GLYPHS: Glyphs = Glyphs {
+    primary_location: "╭▸ ",
+    secondary_file: "⸬ ",
+    col_separator: '│',
+    vertical: '│',
+    horizontal: '─',
+    multiline_start: '╭',
+    multiline_start_corner: Some('╭'),
+    multiline_end_corner: '╰',
+    underline_primary: '━',
+    underline_secondary: '─',
+};
+
+impl Charset {
+    fn glyphs(self) -> &'static Glyphs {
+        match self {
+            Charset::Ascii => &ASCII_GLYPHS,
+            Charset::Unicode => &UNICODE_GLYPHS,
+        }
+    }
+}
+
+/// Which lines of a multiline span are quoted.
+///
+/// The first and last lines are always shown, and the lines left out are replaced with `...`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MultilineFold {
+    /// Show every line of the span.
+    Full,
+
+    /// Show the first `head` and the last `tail` lines of the span.
+    HeadTail {
+        /// Number of lines shown at the start of the span.
+        head: usize,
+        /// Number of lines shown at the end of the span.
+        tail: usize,
+    },
+
+    /// Show only the first and last lines of the span.
+    Endpoints,
+}
+
+impl MultilineFold {
+    /// Whether a line inside a span is shown, given its distance from the first and last lines.
+    fn shows(self, from_start: usize, from_end: usize) -> bool {
+        match self {
+            MultilineFold::Full => true,
+            MultilineFold::HeadTail { head, tail } => from_start < head || from_end < tail,
+            MultilineFold::Endpoints => false,
+        }
+    }
+}
+
+impl Default for MultilineFold {
+    fn default() -> MultilineFold {
+        MultilineFold::HeadTail { head: 4, tail: 2 }
+    }
+}
+
+/// Default number of columns between tab stops when quoting source code.
+const DEFAULT_TAB_WIDTH: usize = 4;
+
+/// Maximum number of lines of modified code shown for a suggestion.
+const MAX_SUGGESTION_HIGHLIGHT_LINES: usize = 6;
+
+/// Width of the `...` marking text cut from a line.
+const ELLIPSIS_WIDTH: usize = 3;
+
 /// Formats and prints diagnostic messages.
 pub struct Emitter<'a> {
     dst: Destination<'a>,
     cm: Option<&'a CodeMap>,
+    short_message: bool,
+    theme: Theme,
+    charset: Charset,
+    tab_width: usize,
+    max_width: Option<usize>,
+    context_lines: usize,
+    gap_threshold: usize,
+    multiline_fold: MultilineFold,
+    highlighter: Option<Box<dyn Highlighter + Send + 'a>>,
+    highlight_backticks: bool,
+    explain: Option<(&'a Registry, String)>,
 }
 
 struct FileWithAnnotatedLines {
@@ -59,34 +187,326 @@ struct FileWithAnnotatedLines {
     multiline_depth: usize,
 }
 
+/// A line of quoted source code.
+#[derive(Clone, Copy, Debug)]
+enum SourceRow {
+    /// The line at this index of `FileWithAnnotatedLines::lines`.
+    Annotated(usize),
+    /// The line at this index of the file, shown without annotations.
+    Unannotated(usize),
+    /// A `...` line replacing lines that are not shown.
+    Elided,
+}
+
+/// The range of display columns of a file's quoted source lines that is shown when the lines
+/// don't fit in the maximum width.
+#[derive(Clone, Copy, Debug)]
+struct Margin {
+    /// First column shown.
+    left: usize,
+    /// Column after the last one shown.
+    right: usize,
+}
+
+impl Margin {
+    /// Show the whole line.
+    const FULL: Margin = Margin { left: 0, right: usize::MAX };
+
+    /// Choose the columns of `annotated_file` to show in `column_width` columns, keeping as much
+    /// of the annotations and their labels visible as possible.
+    ///
+    /// If the annotations don't all fit, the margin is placed around the primary ones.
+    fn compute(annotated_file: &FileWithAnnotatedLines,
+               rows: &[SourceRow],
+               column_width: usize,
+               tab_width: usize)
+               -> Margin {
+        let mut max_line_len = 0;
+        let mut whitespace_left = usize::MAX;
+
+        for &row in rows {
+            let line_index = match row {
+                SourceRow::Annotated(line_idx) => annotated_file.lines[line_idx].line_index,
+                SourceRow::Unannotated(line_index) => line_index,
+                SourceRow::Elided => continue,
+            };
+            let source = annotated_file.file.source_line(line_index);
+            max_line_len = max(max_line_len, display_col(source, source.chars().count(), tab_width));
+            if !source.trim().is_empty() {
+                let indent = source.chars().take_while(|c| c.is_whitespace()).count();
+                whitespace_left = min(whitespace_left, display_col(source, indent, tab_width));
+            }
+        }
+
+        let all = SpanColumns::of(annotated_file, |_| true);
+        if max_line_len <= column_width || all.is_empty() {
+            return Margin::FULL;
+        }
+        let primary = SpanColumns::of(annotated_file, |ann| ann.is_primary);
+        let primary = if primary.is_empty() { all } else { primary };
+        let whitespace_left = min(whitespace_left, all.span_left);
+
+        let (left, span_left) = if all.label_right - whitespace_left <= column_width {
+            (whitespace_left, all.span_left)
+        } else if let Some(left) = all.fit(column_width) {
+            (left, all.span_left)
+        } else if let Some(left) = primary.fit(column_width) {
+            (left, primary.span_left)
+        } else {
+            (primary.span_left, primary.span_left)
+        };
+
+        // Don't leave columns empty past the end of the longest line and its labels
+        let left = min(left, max(max_line_len, all.label_right).saturating_sub(column_width));
+
+        // Don't let the `...` marking the cut cover the start of the annotations
+        let left = if left > 0 {
+            min(left, span_left.saturating_sub(ELLIPSIS_WIDTH))
+        } else {
+            0
+        };
+
+        Margin { left, right: left + column_width }
+    }
+
+    /// Move an annotation's columns to be relative to the left edge of the margin.
+    ///
+    /// Annotations outside the margin are moved under the `...` on that side, so that they and
+    /// their labels are still shown. The horizontal lines of multiline annotations stop at the
+    /// edge.
+    fn shift(&self, ann: &mut Annotation) {
+        let width = self.width();
+        if !ann.is_multiline() {
+            if ann.start_col >= self.right {
+                ann.start_col = width.saturating_sub(ELLIPSIS_WIDTH);
+                ann.end_col = width;
+                return;
+            } else if ann.end_col <= self.left {
+                ann.start_col = 0;
+                ann.end_col = min(ELLIPSIS_WIDTH, width);
+                return;
+            }
+        }
+        ann.start_col = min(ann.start_col.saturating_sub(self.left), width);
+        ann.end_col = min(ann.end_col.saturating_sub(self.left), width);
+    }
+
+    /// Number of columns shown.
+    fn width(&self) -> usize {
+        self.right - self.left
+    }
+}
+
+/// The columns covered by some of the annotations of a file, used to place the margin.
+#[derive(Clone, Copy, Debug)]
+struct SpanColumns {
+    /// First column of the annotations.
+    span_left: usize,
+    /// Column after the end of the annotations.
+    span_right: usize,
+    /// Column after the end of the annotations and their labels.
+    label_right: usize,
+}
+
+impl SpanColumns {
+    fn of<F>(annotated_file: &FileWithAnnotatedLines, filter: F) -> SpanColumns
+        where F: Fn(&Annotation) -> bool
+    {
+        let mut columns = SpanColumns { span_left: usize::MAX, span_right: 0, label_right: 0 };
+        for line in &annotated_file.lines {
+            for ann in line.annotations.iter().filter(|ann| !ann.is_line() && filter(ann)) {
+                columns.span_left = min(columns.span_left, ann.start_col);
+                columns.span_right = max(columns.span_right, ann.end_col);
+                let label_len = ann.label.as_ref().map_or(0, |label| str_width(label) + 1);
+                columns.label_right = max(columns.label_right, ann.end_col + label_len);
+            }
+        }
+        columns
+    }
+
+    fn is_empty(&self) -> bool {
+        self.span_left > self.span_right
+    }
+
+    /// The left edge of a margin `column_width` wide that shows the annotations with their
+    /// labels, or at least the annotations, if either fits.
+    fn fit(&self, column_width: usize) -> Option<usize> {
+        if self.label_right - self.span_left <= column_width {
+            let padding_left = (column_width - (self.label_right - self.span_left)) / 2;
+            Some(self.span_left.saturating_sub(padding_left))
+        } else if self.span_right - self.span_left <= column_width {
+            let padding_left = (column_width - (self.span_right - self.span_left)) / 5 * 2;
+            Some(self.span_left.saturating_sub(padding_left))
+        } else {
+            None
+        }
+    }
+}
+
 impl<'a> Emitter<'a> {
     /// Creates an emitter wrapping stderr.
+    ///
+    /// With `ColorConfig::Auto`, the maximum width defaults to the width of the terminal.
     pub fn stderr(color_config: ColorConfig, code_map: Option<&'a CodeMap>) -> Emitter<'a> {
-        let dst = Destination::from_stderr(color_config);
-        Emitter {
-            dst: dst,
-            cm: code_map,
+        let mut emitter = Emitter::with_destination(Destination::from_stderr(color_config), code_map);
+        if color_config == ColorConfig::Auto {
+            emitter.max_width = terminal_size_of(io::stderr()).map(|(Width(w), _)| w as usize);
         }
+        emitter
     }
 
     /// Creates an emitter wrapping a vector.
     pub fn vec(vec: &'a mut Vec<u8>, code_map: Option<&'a CodeMap>) -> Emitter<'a> {
-        Emitter {
-            dst: Raw(Box::new(vec)),
-            cm: code_map,
-        }
+        Emitter::with_destination(Raw(Box::new(vec)), code_map)
     }
 
     /// Creates an emitter wrapping a boxed `Write` trait object.
-    pub fn new(dst: Box<Write + Send + 'a>, code_map: Option<&'a CodeMap>) -> Emitter<'a> {
+    pub fn new(dst: Box<dyn Write + Send + 'a>, code_map: Option<&'a CodeMap>) -> Emitter<'a> {
+        Emitter::with_destination(Raw(dst), code_map)
+    }
+
+    /// Creates an emitter wrapping a boxed `WriteColor` trait object.
+    ///
+    /// Unlike `Emitter::new`, the output is colored. This can be used to capture colored output,
+    /// for example with `termcolor::Ansi` or `termcolor::Buffer`.
+    pub fn colored(dst: Box<dyn WriteColor + Send + 'a>, code_map: Option<&'a CodeMap>) -> Emitter<'a> {
+        Emitter::with_destination(Colored(dst), code_map)
+    }
+
+    /// Creates an emitter without an output, for use with `render`.
+    ///
+    /// Diagnostics passed to `emit` are discarded.
+    pub fn renderer(code_map: Option<&'a CodeMap>) -> Emitter<'a> {
+        Emitter::new(Box::new(io::sink()), code_map)
+    }
+
+    fn with_destination(dst: Destination<'a>, code_map: Option<&'a CodeMap>) -> Emitter<'a> {
         Emitter {
-            dst: Raw(dst),
+            dst,
             cm: code_map,
+            short_message: false,
+            theme: Theme::default(),
+            charset: Charset::Ascii,
+            tab_width: DEFAULT_TAB_WIDTH,
+            max_width: None,
+            context_lines: 0,
+            gap_threshold: 1,
+            multiline_fold: MultilineFold::default(),
+            highlighter: None,
+            highlight_backticks: false,
+            explain: None,
+        }
+    }
+
+    /// Print each diagnostic on a single line, in the `file:line:col: level: message` format used
+    /// by GCC and Clang, instead of quoting the annotated source code.
+    ///
+    /// Labelled secondary spans are printed as additional `note` lines.
+    pub fn short_message(mut self, short_message: bool) -> Emitter<'a> {
+        self.short_message = short_message;
+        self
+    }
+
+    /// Set the colors used for colored output.
+    pub fn theme(mut self, theme: Theme) -> Emitter<'a> {
+        self.theme = theme;
+        self
+    }
+
+    /// Set the characters used to draw the margin and underlines. The default is `Charset::Ascii`.
+    pub fn charset(mut self, charset: Charset) -> Emitter<'a> {
+        self.charset = charset;
+        self
+    }
+
+    /// Set the number of columns between tab stops used to expand tabs in quoted source code.
+    /// The default is 4.
+    ///
+    /// A width of 0 removes tabs from the quoted code instead of expanding them.
+    pub fn tab_width(mut self, tab_width: usize) -> Emitter<'a> {
+        self.tab_width = tab_width;
+        self
+    }
+
+    /// Set the maximum width of the output in columns, or `None` for no limit.
+    ///
+    /// Quoted source lines that don't fit are cut around the annotated columns, with `...`
+    /// marking the removed text.
+    pub fn max_width(mut self, max_width: Option<usize>) -> Emitter<'a> {
+        self.max_width = max_width;
+        self
+    }
+
+    /// Set the number of unannotated lines of source code shown before and after each annotated
+    /// line. The default is 0.
+    pub fn context_lines(mut self, context_lines: usize) -> Emitter<'a> {
+        self.context_lines = context_lines;
+        self
+    }
+
+    /// Set the largest number of consecutive lines of source code that are shown between quoted
+    /// lines rather than replaced with `...`. The default is 1.
+    pub fn gap_threshold(mut self, gap_threshold: usize) -> Emitter<'a> {
+        self.gap_threshold = gap_threshold;
+        self
+    }
+
+    /// Set which lines of multiline spans are quoted. The default shows the first 4 and last 2
+    /// lines.
+    pub fn multiline_fold(mut self, multiline_fold: MultilineFold) -> Emitter<'a> {
+        self.multiline_fold = multiline_fold;
+        self
+    }
+
+    /// Set a highlighter to colorize quoted source code.
+    pub fn highlighter(mut self, highlighter: Box<dyn Highlighter + Send + 'a>) -> Emitter<'a> {
+        self.highlighter = Some(highlighter);
+        self
+    }
+
+    /// Emphasize text between backticks in messages and span labels with `Style::Highlight`.
+    ///
+    /// The backticks are kept, so the text is unchanged in uncolored output.
+    pub fn highlight_backticks(mut self, highlight_backticks: bool) -> Emitter<'a> {
+        self.highlight_backticks = highlight_backticks;
+        self
+    }
+
+    /// At the end of each group, print a footer pointing to the explanation of each code from
+    /// `registry` used in the group, such as "for more information about this error, try
+    /// `tool --explain C000`", where `command` is `tool --explain`.
+    pub fn explain_footer(mut self, registry: &'a Registry, command: &str) -> Emitter<'a> {
+        self.explain = Some((registry, command.to_owned()));
+        self
+    }
+
+    /// Split text into the parts to display, with `style` or emphasized if it is between
+    /// backticks.
+    fn text_parts(&self, text: &str, style: Style) -> Vec<(String, Style)> {
+        if self.highlight_backticks {
+            split_backticks(text, style, Style::Highlight)
+        } else {
+            vec![(text.to_owned(), style)]
         }
     }
 
-    fn preprocess_annotations(cm: Option<&'a CodeMap>, spans: &[SpanLabel]) -> Vec<FileWithAnnotatedLines> {
-        fn add_annotation_to_file<'a>(file_vec: &mut Vec<FileWithAnnotatedLines>,
+    /// The parts of a message to display, with unstyled text between backticks emphasized.
+    fn message_parts(&self, message: &Message) -> Vec<(String, Style)> {
+        message.parts.iter().flat_map(|&(ref text, style)| {
+            if style == Style::NoStyle {
+                self.text_parts(text, style)
+            } else {
+                vec![(text.clone(), style)]
+            }
+        }).collect()
+    }
+
+    fn preprocess_annotations(cm: Option<&'a CodeMap>,
+                              spans: &[SpanLabel],
+                              tab_width: usize,
+                              multiline_fold: MultilineFold)
+                              -> Vec<FileWithAnnotatedLines> {
+        fn add_annotation_to_file(file_vec: &mut Vec<FileWithAnnotatedLines>,
                                   file: Arc<File>,
                                   line_index: usize,
                                   ann: Annotation) {
@@ -103,7 +523,7 @@ impl<'a> Emitter<'a> {
                     }
                     // We don't have a line yet, create one
                     slot.lines.push(Line {
-                        line_index: line_index,
+                        line_index,
                         annotations: vec![ann],
                     });
                     slot.lines.sort();
@@ -112,9 +532,9 @@ impl<'a> Emitter<'a> {
             }
             // This is the first time we're seeing the file
             file_vec.push(FileWithAnnotatedLines {
-                file: file,
+                file,
                 lines: vec![Line {
-                                line_index: line_index,
+                                line_index,
                                 annotations: vec![ann],
                             }],
                 multiline_depth: 0,
@@ -124,16 +544,26 @@ impl<'a> Emitter<'a> {
         let mut output = vec![];
         let mut multiline_annotations = vec![];
 
-        if let Some(ref cm) = cm {
+        if let Some(cm) = cm {
             for span_label in spans {
                 let mut loc = cm.look_up_span(span_label.span);
 
+                // Columns are counted in characters, but annotations are positioned by the
+                // width of the text when displayed in a terminal, with tabs expanded.
+                loc.begin.column = display_col(loc.file.source_line(loc.begin.line),
+                                               loc.begin.column,
+                                               tab_width);
+                loc.end.column = display_col(loc.file.source_line(loc.end.line),
+                                             loc.end.column,
+                                             tab_width);
+
                 // Watch out for "empty spans". If we get a span like 6..6, we
                 // want to just display a `^` at 6, so convert that to
                 // 6..7. This is degenerate input, but it's best to degrade
                 // gracefully -- and the parser likes to supply a span like
-                // that for EOF, in particular.
-                if loc.begin == loc.end {
+                // that for EOF, in particular. Spans covering only zero-width
+                // characters are treated the same way.
+                if loc.begin.line == loc.end.line && loc.end.column <= loc.begin.column {
                     loc.end.column = loc.begin.column + 1;
                 }
 
@@ -176,7 +606,7 @@ impl<'a> Emitter<'a> {
         for item in multiline_annotations.clone() {
             let ann = item.1;
             for item in multiline_annotations.iter_mut() {
-                let ref mut a = item.1;
+                let a = &mut item.1;
                 // Move all other multiline annotations overlapping with this one
                 // one level to the right.
                 if &ann != a &&
@@ -195,12 +625,8 @@ impl<'a> Emitter<'a> {
                 max_depth = ann.depth;
             }
             add_annotation_to_file(&mut output, file.clone(), ann.line_start, ann.as_start());
-            let middle = min(ann.line_start + 4, ann.line_end);
-            for line in ann.line_start + 1..middle {
-                add_annotation_to_file(&mut output, file.clone(), line, ann.as_line());
-            }
-            if middle < ann.line_end - 1 {
-                for line in ann.line_end - 1..ann.line_end {
+            for line in ann.line_start + 1..ann.line_end {
+                if multiline_fold.shows(line - ann.line_start, ann.line_end - line) {
                     add_annotation_to_file(&mut output, file.clone(), line, ann.as_line());
                 }
             }
@@ -217,20 +643,22 @@ impl<'a> Emitter<'a> {
                           file: &File,
                           line: &Line,
                           width_offset: usize,
-                          code_offset: usize) -> Vec<(usize, Style)> {
+                          code_offset: usize,
+                          margin: Margin) -> Vec<(usize, Style)> {
 
+        let glyphs = self.charset.glyphs();
         let source_string = file.source_line(line.line_index);
 
         let line_offset = buffer.num_lines();
 
         // First create the source line we will highlight.
-        buffer.puts(line_offset, code_offset, &source_string, Style::Quotation);
+        self.quote_source_line(buffer, line_offset, code_offset, file, line.line_index, margin);
         buffer.puts(line_offset,
                     0,
                     &((line.line_index + 1).to_string()),
                     Style::LineNumber);
 
-        draw_col_separator(buffer, line_offset, width_offset - 2);
+        draw_col_separator(buffer, line_offset, width_offset - 2, glyphs);
 
         // Special case when there's only one annotation involved, it is the start of a multiline
         // span and there's no text at the beginning of the code line. Instead of doing the whole
@@ -249,11 +677,12 @@ impl<'a> Emitter<'a> {
         // 4 | | }
         //   | |_^ test
         if line.annotations.len() == 1 {
-            if let Some(ref ann) = line.annotations.get(0) {
+            if let Some(ann) = line.annotations.first() {
                 if let AnnotationType::MultilineStart(depth) = ann.annotation_type {
-                    if source_string.chars()
-                                    .take(ann.start_col)
-                                    .all(|c| c.is_whitespace()) {
+                    let indent = source_string.chars()
+                                              .take_while(|c| c.is_whitespace())
+                                              .count();
+                    if display_col(source_string, indent, self.tab_width) >= ann.start_col {
                         let style = if ann.is_primary {
                             Style::UnderlinePrimary
                         } else {
@@ -261,7 +690,7 @@ impl<'a> Emitter<'a> {
                         };
                         buffer.putc(line_offset,
                                     width_offset + depth - 1,
-                                    '/',
+                                    glyphs.multiline_start,
                                     style);
                         return vec![(depth, style)];
                     }
@@ -301,7 +730,10 @@ impl<'a> Emitter<'a> {
         // the left, so the rightmost span needs to be rendered first,
         // otherwise the lines would end up needing to go over a message.
         let mut annotations = line.annotations.clone();
-        annotations.sort_by(|a,b| b.start_col.cmp(&a.start_col));
+        for ann in &mut annotations {
+            margin.shift(ann);
+        }
+        annotations.sort_by_key(|a| ::std::cmp::Reverse(a.start_col));
 
         // First, figure out where each label will be positioned.
         //
@@ -384,7 +816,7 @@ impl<'a> Emitter<'a> {
             for (j, next) in annotations.iter().enumerate() {
                 if j > i  {
                     let l = if let Some(ref label) = next.label {
-                        label.len() + 2
+                        str_width(label) + 2
                     } else {
                         0
                     };
@@ -428,9 +860,7 @@ impl<'a> Emitter<'a> {
 
         // If there are no annotations or the only annotations on this line are
         // MultilineLine, then there's only code being shown, stop processing.
-        if line.annotations.is_empty() || line.annotations.iter()
-            .filter(|a| !a.is_line()).collect::<Vec<_>>().len() == 0
-        {
+        if annotations.iter().all(|a| a.is_line()) {
             return vec![];
         }
 
@@ -446,11 +876,7 @@ impl<'a> Emitter<'a> {
         // 4 |   }
         //   |
         for pos in 0..line_len + 1 {
-            draw_col_separator(buffer, line_offset + pos + 1, width_offset - 2);
-            buffer.putc(line_offset + pos + 1,
-                        width_offset - 2,
-                        '|',
-                        Style::LineNumber);
+            draw_col_separator(buffer, line_offset + pos + 1, width_offset - 2, glyphs);
         }
 
         // Write the horizontal lines for multiline annotations
@@ -476,7 +902,7 @@ impl<'a> Emitter<'a> {
                 AnnotationType::MultilineStart(depth) |
                 AnnotationType::MultilineEnd(depth) => {
                     draw_range(buffer,
-                               '_',
+                               glyphs.horizontal,
                                line_offset + pos,
                                width_offset + depth,
                                code_offset + annotation.start_col,
@@ -509,26 +935,33 @@ impl<'a> Emitter<'a> {
                 for p in line_offset + 1..line_offset + pos + 1 {
                     buffer.putc(p,
                                 code_offset + annotation.start_col,
-                                '|',
+                                glyphs.vertical,
                                 style);
                 }
             }
             match annotation.annotation_type {
                 AnnotationType::MultilineStart(depth) => {
+                    if let Some(corner) = glyphs.multiline_start_corner {
+                        buffer.putc(line_offset + pos, width_offset + depth - 1, corner, style);
+                    }
                     for p in line_offset + pos + 1..line_offset + line_len + 2 {
                         buffer.putc(p,
                                     width_offset + depth - 1,
-                                    '|',
+                                    glyphs.vertical,
                                     style);
                     }
                 }
                 AnnotationType::MultilineEnd(depth) => {
-                    for p in line_offset..line_offset + pos + 1 {
+                    for p in line_offset..line_offset + pos {
                         buffer.putc(p,
                                     width_offset + depth - 1,
-                                    '|',
+                                    glyphs.vertical,
                                     style);
                     }
+                    buffer.putc(line_offset + pos,
+                                width_offset + depth - 1,
+                                glyphs.multiline_end_corner,
+                                style);
                 }
                 _ => (),
             }
@@ -557,10 +990,20 @@ impl<'a> Emitter<'a> {
                 (pos + 2, annotation.start_col)
             };
             if let Some(ref label) = annotation.label {
-                buffer.puts(line_offset + pos,
-                            code_offset + col,
-                            &label,
-                            style);
+                // Cut labels that would run past the right edge of the margin, unless none of
+                // the label would be left
+                let mut col = col;
+                let mut parts = self.text_parts(label, style);
+                let label_width: usize = parts.iter().map(|(text, _)| str_width(text)).sum();
+                if col + label_width > margin.width() && col + ELLIPSIS_WIDTH < margin.width() {
+                    let visible = margin.width().saturating_sub(col + ELLIPSIS_WIDTH);
+                    parts = cut_parts(parts, visible);
+                    parts.push(("...".to_owned(), Style::LineNumber));
+                }
+                for (text, style) in parts {
+                    buffer.puts(line_offset + pos, code_offset + col, &text, style);
+                    col += str_width(&text);
+                }
             }
         }
 
@@ -590,9 +1033,9 @@ impl<'a> Emitter<'a> {
         //   |  _^  test
         for &(_, annotation) in &annotations_position {
             let (underline, style) = if annotation.is_primary {
-                ('^', Style::UnderlinePrimary)
+                (glyphs.underline_primary, Style::UnderlinePrimary)
             } else {
-                ('-', Style::UnderlineSecondary)
+                (glyphs.underline_secondary, Style::UnderlineSecondary)
             };
             for p in annotation.start_col..annotation.end_col {
                 buffer.putc(line_offset + 1,
@@ -617,12 +1060,90 @@ impl<'a> Emitter<'a> {
         }).collect::<Vec<_>>()
     }
 
-    fn get_max_line_num(&mut self, diagnostics: &[Diagnostic]) -> usize {
-        if let Some(ref cm) = self.cm {
+    /// Choose the lines of a file to quote: the annotated lines, the context lines around them,
+    /// and the lines between them unless there are more than `gap_threshold`.
+    fn source_rows(&self, annotated_file: &FileWithAnnotatedLines) -> Vec<SourceRow> {
+        let lines = &annotated_file.lines;
+        let num_lines = annotated_file.file.num_lines();
+        let mut rows = vec![];
+
+        // The lines before this one have already been shown or elided
+        let mut shown_end = 0;
+
+        for (line_idx, line) in lines.iter().enumerate() {
+            let mut start = max(line.line_index.saturating_sub(self.context_lines), shown_end);
+            if line_idx > 0 && start > shown_end {
+                if start - shown_end > self.gap_threshold {
+                    rows.push(SourceRow::Elided);
+                } else {
+                    start = shown_end;
+                }
+            }
+            rows.extend((start..line.line_index).map(SourceRow::Unannotated));
+
+            rows.push(SourceRow::Annotated(line_idx));
+
+            let next = lines.get(line_idx + 1).map_or(num_lines, |next| next.line_index);
+            let end = min(line.line_index + 1 + self.context_lines, min(next, num_lines));
+            rows.extend((line.line_index + 1..end).map(SourceRow::Unannotated));
+            shown_end = end;
+        }
+
+        rows
+    }
+
+    /// Write the part of a source line within the margin, marking removed text with `...`.
+    fn quote_source_line(&self,
+                         buffer: &mut StyledBuffer,
+                         line: usize,
+                         code_offset: usize,
+                         file: &File,
+                         line_index: usize,
+                         margin: Margin) {
+        let source = file.source_line(line_index);
+        let expanded = expand_tabs(source, self.tab_width);
+        buffer.puts(line, code_offset, &slice_columns(&expanded, margin.left, margin.right), Style::Quotation);
+        if margin.left > 0 {
+            buffer.puts(line, code_offset, "...", Style::LineNumber);
+        }
+        if str_width(&expanded) > margin.right {
+            buffer.puts(line, code_offset + margin.width() - ELLIPSIS_WIDTH, "...", Style::LineNumber);
+        }
+
+        if let Some(ref highlighter) = self.highlighter {
+            for (range, class) in highlighter.highlight_line(file, line_index) {
+                let (start, end) = match (source.get(..range.start), source.get(..range.end)) {
+                    (Some(start), Some(end)) => (start, end),
+                    _ => continue,
+                };
+                let start_col = display_col(source, start.chars().count(), self.tab_width);
+                let end_col = display_col(source, end.chars().count(), self.tab_width);
+                let start_col = max(start_col, margin.left) - margin.left;
+                let end_col = min(end_col, margin.right).saturating_sub(margin.left);
+                buffer.set_style_range(line,
+                                       code_offset + start_col,
+                                       code_offset + end_col,
+                                       Style::Syntax(class),
+                                       false);
+            }
+        }
+    }
+
+    fn get_max_line_num(&self, diagnostics: &[Diagnostic]) -> usize {
+        let max_line_num = |cm: &CodeMap, spans: &[SpanLabel]| -> usize {
+            spans.iter().map(|span_label| {
+                let loc = cm.look_up_pos(span_label.span.high());
+                min(loc.position.line + self.context_lines, loc.file.num_lines().saturating_sub(1))
+            }).max().unwrap_or(0)
+        };
+
+        if let Some(cm) = self.cm {
             diagnostics.iter().map(|d| {
-                d.spans.iter().map(|span_label| {
-                    cm.look_up_pos(span_label.span.high()).position.line
-                }).max().unwrap_or(0)
+                let children = d.children.iter().map(|c| max_line_num(cm, &c.spans));
+                let suggestions = d.suggestions.iter().map(|s| {
+                    cm.look_up_pos(s.span.high()).position.line + s.replacement.matches('\n').count()
+                });
+                children.chain(suggestions).fold(max_line_num(cm, &d.spans), ::std::cmp::max)
             }).max().unwrap_or(0)
         } else { 0 }
     }
@@ -631,7 +1152,7 @@ impl<'a> Emitter<'a> {
     /// displayed, keeping the provided highlighting.
     fn msg_to_buffer(&self,
                      buffer: &mut StyledBuffer,
-                     msg: &Vec<(String, Style)>,
+                     msg: &[(String, Style)],
                      padding: usize,
                      label: &str,
                      override_style: Option<Style>) {
@@ -656,16 +1177,6 @@ impl<'a> Emitter<'a> {
             .map(|_| " ")
             .collect::<String>();
 
-        /// Return wether `style`, or the override if present and the style is `NoStyle`.
-        fn style_or_override(style: Style, override_style: Option<Style>) -> Style {
-            if let Some(o) = override_style {
-                if style == Style::NoStyle {
-                    return o;
-                }
-            }
-            style
-        }
-
         let mut line_number = 0;
 
         // Provided the following diagnostic message:
@@ -687,7 +1198,7 @@ impl<'a> Emitter<'a> {
         //                see how it *looks* with
         //                very *weird* formats
         //                see?
-        for &(ref text, ref style) in msg.iter() {
+        for (text, style) in msg.iter() {
             let lines = text.split('\n').collect::<Vec<_>>();
             if lines.len() > 1 {
                 for (i, line) in lines.iter().enumerate() {
@@ -703,17 +1214,18 @@ impl<'a> Emitter<'a> {
         }
     }
 
-    fn emit_message_default(&mut self,
-                            spans: &[SpanLabel],
-                            msg: &Vec<(String, Style)>,
-                            code: &Option<String>,
-                            level: &Level,
-                            max_line_num_len: usize,
-                            is_secondary: bool)
-                            -> io::Result<()> {
+    fn render_message_default(&self,
+                              spans: &[SpanLabel],
+                              msg: &[(String, Style)],
+                              code: &Option<String>,
+                              level: &Level,
+                              max_line_num_len: usize,
+                              is_secondary: bool)
+                              -> Vec<Vec<StyledString>> {
+        let glyphs = self.charset.glyphs();
         let mut buffer = StyledBuffer::new();
 
-        if is_secondary && spans.len() == 0 {
+        if is_secondary && spans.is_empty() {
             // This is a secondary message with no span info
             for _ in 0..max_line_num_len {
                 buffer.prepend(0, " ", Style::NoStyle);
@@ -721,35 +1233,37 @@ impl<'a> Emitter<'a> {
             draw_note_separator(&mut buffer, 0, max_line_num_len + 1);
             buffer.append(0, &level.to_string(), Style::HeaderMsg);
             buffer.append(0, ": ", Style::NoStyle);
-            self.msg_to_buffer(&mut buffer, msg, max_line_num_len, "note", None);
+            self.msg_to_buffer(&mut buffer, msg, max_line_num_len, level.to_str(), None);
         } else {
-            buffer.append(0, &level.to_string(), Style::Level(level.clone()));
+            buffer.append(0, &level.to_string(), Style::Level(*level));
             if let Some(code) = code.as_ref() {
-                buffer.append(0, "[", Style::Level(level.clone()));
-                buffer.append(0, &code, Style::Level(level.clone()));
-                buffer.append(0, "]", Style::Level(level.clone()));
+                buffer.append(0, "[", Style::Level(*level));
+                buffer.append(0, code, Style::Level(*level));
+                buffer.append(0, "]", Style::Level(*level));
             }
             buffer.append(0, ": ", Style::HeaderMsg);
-            for &(ref text, _) in msg.iter() {
-                buffer.append(0, text, Style::HeaderMsg);
+            for (text, style) in msg.iter() {
+                buffer.append(0, text, style_or_override(*style, Some(Style::HeaderMsg)));
             }
         }
 
         // Preprocess all the annotations so that they are grouped by file and by line number
         // This helps us quickly iterate over the whole message (including secondary file spans)
-        let mut annotated_files = Emitter::preprocess_annotations(self.cm, spans);
+        let mut annotated_files = Emitter::preprocess_annotations(self.cm,
+                                                                  spans,
+                                                                  self.tab_width,
+                                                                  self.multiline_fold);
 
         // Make sure our primary file comes first
-        let primary_lo = if let (Some(ref cm), Some(ref primary_span)) =
+        let primary_lo = if let (Some(cm), Some(primary_span)) =
             (self.cm.as_ref(), spans.iter().find(|x| x.style == SpanStyle::Primary)) {
             cm.look_up_pos(primary_span.span.low())
         } else {
             // If we don't have span information, emit and exit
-            emit_to_destination(&buffer.render(), level, &mut self.dst)?;
-            return Ok(());
+            return buffer.render();
         };
         if let Ok(pos) =
-            annotated_files.binary_search_by(|x| x.file.name().cmp(&primary_lo.file.name())) {
+            annotated_files.binary_search_by(|x| x.file.name().cmp(primary_lo.file.name())) {
             annotated_files.swap(0, pos);
         }
 
@@ -762,7 +1276,7 @@ impl<'a> Emitter<'a> {
                 // remember where we are in the output buffer for easy reference
                 let buffer_msg_line_offset = buffer.num_lines();
 
-                buffer.prepend(buffer_msg_line_offset, "--> ", Style::LineNumber);
+                buffer.prepend(buffer_msg_line_offset, glyphs.primary_location, Style::LineNumber);
                 let loc = primary_lo.clone();
                 buffer.append(buffer_msg_line_offset,
                               &format!("{}:{}:{}", loc.file.name(), loc.position.line + 1, loc.position.column + 1),
@@ -775,10 +1289,10 @@ impl<'a> Emitter<'a> {
                 let buffer_msg_line_offset = buffer.num_lines();
 
                 // Add spacing line
-                draw_col_separator(&mut buffer, buffer_msg_line_offset, max_line_num_len + 1);
+                draw_col_separator(&mut buffer, buffer_msg_line_offset, max_line_num_len + 1, glyphs);
 
                 // Then, the secondary file indicator
-                buffer.prepend(buffer_msg_line_offset + 1, "::: ", Style::LineNumber);
+                buffer.prepend(buffer_msg_line_offset + 1, glyphs.secondary_file, Style::LineNumber);
                 buffer.append(buffer_msg_line_offset + 1,
                               annotated_file.file.name(),
                               Style::LineAndColumn);
@@ -789,145 +1303,427 @@ impl<'a> Emitter<'a> {
 
             // Put in the spacer between the location and annotated source
             let buffer_msg_line_offset = buffer.num_lines();
-            draw_col_separator_no_space(&mut buffer, buffer_msg_line_offset, max_line_num_len + 1);
+            draw_col_separator_no_space(&mut buffer, buffer_msg_line_offset, max_line_num_len + 1, glyphs);
 
             // Contains the vertical lines' positions for active multiline annotations
             let mut multilines = HashMap::new();
 
+            let width_offset = 3 + max_line_num_len;
+            let code_offset = if annotated_file.multiline_depth == 0 {
+                width_offset
+            } else {
+                width_offset + annotated_file.multiline_depth + 1
+            };
+
+            let rows = self.source_rows(&annotated_file);
+
+            // Leave room for at least a few columns of code, even if the margin is wider
+            let margin = match self.max_width {
+                Some(max_width) => {
+                    let column_width = max(max_width.saturating_sub(code_offset), 8);
+                    Margin::compute(&annotated_file, &rows, column_width, self.tab_width)
+                }
+                None => Margin::FULL,
+            };
+
             // Next, output the annotate source for this file
-            for line_idx in 0..annotated_file.lines.len() {
+            for row in rows {
                 let previous_buffer_line = buffer.num_lines();
 
-                let width_offset = 3 + max_line_num_len;
-                let code_offset = if annotated_file.multiline_depth == 0 {
-                    width_offset
-                } else {
-                    width_offset + annotated_file.multiline_depth + 1
-                };
-
-                let depths = self.render_source_line(&mut buffer,
-                                                     &annotated_file.file,
-                                                     &annotated_file.lines[line_idx],
-                                                     width_offset,
-                                                     code_offset);
+                match row {
+                    SourceRow::Annotated(line_idx) => {
+                        let depths = self.render_source_line(&mut buffer,
+                                                             &annotated_file.file,
+                                                             &annotated_file.lines[line_idx],
+                                                             width_offset,
+                                                             code_offset,
+                                                             margin);
+
+                        let mut to_add = HashMap::new();
+
+                        for (depth, style) in depths {
+                            if multilines.contains_key(&depth) {
+                                multilines.remove(&depth);
+                            } else {
+                                to_add.insert(depth, style);
+                            }
+                        }
 
-                let mut to_add = HashMap::new();
+                        // Set the multiline annotation vertical lines to the left of
+                        // the code in this line.
+                        for (depth, style) in &multilines {
+                            for line in previous_buffer_line..buffer.num_lines() {
+                                draw_multiline_line(&mut buffer,
+                                                    line,
+                                                    width_offset,
+                                                    *depth,
+                                                    *style,
+                                                    glyphs);
+                            }
+                        }
 
-                for (depth, style) in depths {
-                    if multilines.get(&depth).is_some() {
-                        multilines.remove(&depth);
-                    } else {
-                        to_add.insert(depth, style);
+                        multilines.extend(&to_add);
+                        continue;
+                    }
+                    SourceRow::Unannotated(line_index) => {
+                        buffer.puts(previous_buffer_line,
+                                    0,
+                                    &(line_index + 1).to_string(),
+                                    Style::LineNumber);
+                        draw_col_separator(&mut buffer, previous_buffer_line, 1 + max_line_num_len, glyphs);
+                        self.quote_source_line(&mut buffer,
+                                               previous_buffer_line,
+                                               code_offset,
+                                               &annotated_file.file,
+                                               line_index,
+                                               margin);
+                    }
+                    SourceRow::Elided => {
+                        buffer.puts(previous_buffer_line, 0, "...", Style::LineNumber);
                     }
                 }
 
-                // Set the multiline annotation vertical lines to the left of
-                // the code in this line.
+                // Set the multiline annotation vertical lines on the bridging line.
                 for (depth, style) in &multilines {
-                    for line in previous_buffer_line..buffer.num_lines() {
-                        draw_multiline_line(&mut buffer,
-                                            line,
-                                            width_offset,
-                                            *depth,
-                                            *style);
-                    }
+                    draw_multiline_line(&mut buffer,
+                                        previous_buffer_line,
+                                        width_offset,
+                                        *depth,
+                                        *style,
+                                        glyphs);
                 }
-                // check to see if we need to print out or elide lines that come between
-                // this annotated line and the next one.
-                if line_idx < (annotated_file.lines.len() - 1) {
-                    let line_idx_delta = annotated_file.lines[line_idx + 1].line_index -
-                                         annotated_file.lines[line_idx].line_index;
-                    if line_idx_delta > 2 {
-                        let last_buffer_line_num = buffer.num_lines();
-                        buffer.puts(last_buffer_line_num, 0, "...", Style::LineNumber);
-
-                        // Set the multiline annotation vertical lines on `...` bridging line.
-                        for (depth, style) in &multilines {
-                            draw_multiline_line(&mut buffer,
-                                                last_buffer_line_num,
-                                                width_offset,
-                                                *depth,
-                                                *style);
-                        }
-                    } else if line_idx_delta == 2 {
-                        let unannotated_line = annotated_file.file
-                            .source_line(annotated_file.lines[line_idx].line_index);
+            }
+        }
 
-                        let last_buffer_line_num = buffer.num_lines();
+        // final step: take our styled buffer and render it
+        buffer.render()
+    }
 
-                        buffer.puts(last_buffer_line_num,
-                                    0,
-                                    &(annotated_file.lines[line_idx + 1].line_index - 1)
-                                        .to_string(),
-                                    Style::LineNumber);
-                        draw_col_separator(&mut buffer, last_buffer_line_num, 1 + max_line_num_len);
-                        buffer.puts(last_buffer_line_num,
-                                    code_offset,
-                                    &unannotated_line,
-                                    Style::Quotation);
+    fn render_messages_default(&self,
+                               msg: &Diagnostic,
+                               max_line_num_len: usize,
+                               output: &mut Vec<(Level, Vec<Vec<StyledString>>)>) {
+        output.push((msg.level, self.render_message_default(&msg.spans[..],
+                                                            &self.message_parts(&msg.message),
+                                                            &msg.code,
+                                                            &msg.level,
+                                                            max_line_num_len,
+                                                            false)));
+
+        if !msg.children.is_empty() || !msg.suggestions.is_empty() {
+            let mut buffer = StyledBuffer::new();
+            draw_col_separator_no_space(&mut buffer, 0, max_line_num_len + 1, self.charset.glyphs());
+            output.push((msg.level, buffer.render()));
+        }
 
-                        for (depth, style) in &multilines {
-                            draw_multiline_line(&mut buffer,
-                                                last_buffer_line_num,
-                                                width_offset,
-                                                *depth,
-                                                *style);
-                        }
+        for child in &msg.children {
+            output.push((child.level, self.render_message_default(&child.spans[..],
+                                                                  &self.message_parts(&child.message),
+                                                                  &None,
+                                                                  &child.level,
+                                                                  max_line_num_len,
+                                                                  true)));
+        }
+
+        for suggestion in &msg.suggestions {
+            output.push((Level::Help, self.render_suggestion_default(suggestion, max_line_num_len)));
+        }
+    }
+
+    /// Render the footers pointing to the explanations of the codes used in a group, once for
+    /// each code.
+    fn render_explain_footers(&self,
+                              msgs: &[Diagnostic],
+                              output: &mut Vec<(Level, Vec<Vec<StyledString>>)>) {
+        let (registry, command) = match self.explain {
+            Some((registry, ref command)) => (registry, command),
+            None => return,
+        };
+
+        let mut seen: Vec<&str> = vec![];
+        for msg in msgs {
+            let code = match msg.code {
+                Some(ref code) if !seen.contains(&&code[..]) => code,
+                _ => continue,
+            };
+            seen.push(code);
+            if registry.find_explanation(code).is_none() {
+                continue;
+            }
+
+            let kind = match msg.level {
+                Level::Bug | Level::Error => "error",
+                Level::Warning => "warning",
+                Level::Note => "note",
+                Level::Help => "help message",
+            };
+            let footer = format!("for more information about this {}, try `{} {}`",
+                                 kind, command, code);
+            let mut buffer = StyledBuffer::new();
+            for (text, style) in self.text_parts(&footer, Style::HeaderMsg) {
+                buffer.append(0, &text, style);
+            }
+            output.push((msg.level, buffer.render()));
+        }
+    }
+
+    fn render_message_short(&self, msg: &Diagnostic) -> Vec<Vec<StyledString>> {
+        let mut buffer = StyledBuffer::new();
+
+        fn loc_to_buffer(buffer: &mut StyledBuffer, line: usize, cm: &CodeMap, span_label: &SpanLabel) {
+            let loc = cm.look_up_pos(span_label.span.low());
+            buffer.append(line,
+                          &format!("{}:{}:{}: ", loc.file.name(), loc.position.line + 1, loc.position.column + 1),
+                          Style::LineAndColumn);
+        }
+
+        if let (Some(cm), Some(primary_span)) =
+            (self.cm, msg.spans.iter().find(|x| x.style == SpanStyle::Primary)) {
+            loc_to_buffer(&mut buffer, 0, cm, primary_span);
+        }
+        buffer.append(0, msg.level.to_str(), Style::Level(msg.level));
+        if let Some(ref code) = msg.code {
+            buffer.append(0, "[", Style::Level(msg.level));
+            buffer.append(0, code, Style::Level(msg.level));
+            buffer.append(0, "]", Style::Level(msg.level));
+        }
+        buffer.append(0, ": ", Style::HeaderMsg);
+        for (text, style) in self.message_parts(&msg.message) {
+            buffer.append(0, &text, style_or_override(style, Some(Style::HeaderMsg)));
+        }
+
+        if let Some(cm) = self.cm {
+            for span_label in msg.spans.iter().filter(|x| x.style == SpanStyle::Secondary) {
+                if let Some(ref label) = span_label.label {
+                    let line = buffer.num_lines();
+                    loc_to_buffer(&mut buffer, line, cm, span_label);
+                    buffer.append(line, Level::Note.to_str(), Style::Level(Level::Note));
+                    buffer.append(line, ": ", Style::HeaderMsg);
+                    for (text, style) in self.text_parts(label, Style::NoStyle) {
+                        buffer.append(line, &text, style);
                     }
                 }
-
-                multilines.extend(&to_add);
             }
         }
 
-        // final step: take our styled buffer, render it, then output it
-        emit_to_destination(&buffer.render(), level, &mut self.dst)?;
+        buffer.render()
+    }
+
+    fn render_suggestion_default(&self,
+                                 suggestion: &Suggestion,
+                                 max_line_num_len: usize)
+                                 -> Vec<Vec<StyledString>> {
+        let level = Level::Help;
+        let glyphs = self.charset.glyphs();
+        let mut buffer = StyledBuffer::new();
+
+        buffer.append(0, level.to_str(), Style::Level(level));
+        buffer.append(0, ": ", Style::HeaderMsg);
+        for (text, style) in self.message_parts(&suggestion.message) {
+            buffer.append(0, &text, style_or_override(style, Some(Style::HeaderMsg)));
+        }
+
+        if let Some(cm) = self.cm {
+            let loc = cm.look_up_span(suggestion.span);
+
+            // Deletions are shown on the original code, with the removed text marked. Insertions
+            // and replacements are shown on the modified code, with the new text marked.
+            let (marked, underline, style) = if suggestion.replacement.is_empty() &&
+                                                loc.begin != loc.end {
+                (loc.file.source_slice(suggestion.span), '-', Style::Removal)
+            } else if loc.begin == loc.end {
+                (&suggestion.replacement[..], '+', Style::Addition)
+            } else {
+                (&suggestion.replacement[..], '~', Style::Addition)
+            };
+
+            // Lines of code to display, with the start and end columns of the marked text
+            let mut lines: Vec<(String, usize, usize)> = vec![];
+            let mut line: String = loc.file.source_line(loc.begin.line)
+                .chars().take(loc.begin.column).collect();
+            let mut start_col = loc.begin.column;
+            for (i, part) in marked.split('\n').enumerate() {
+                let indent = part.chars().take_while(|c| c.is_whitespace()).count();
+                if i != 0 {
+                    let end_col = line.chars().count();
+                    lines.push((line, start_col, end_col));
+                    line = String::new();
+                    // Don't mark the indentation of continuation lines
+                    start_col = indent;
+                } else if line.chars().all(char::is_whitespace) {
+                    // Nor of a first line that starts at the beginning of the code
+                    start_col += indent;
+                }
+                line.push_str(part.trim_end_matches('\r'));
+            }
+            let end_col = line.chars().count();
+            line.extend(loc.file.source_line(loc.end.line).chars().skip(loc.end.column));
+            lines.push((line, start_col, end_col));
+
+            // Convert the character columns to display columns
+            let lines: Vec<(String, usize, usize)> = lines.into_iter().map(|(line, start, end)| {
+                let start_col = display_col(&line, start, self.tab_width);
+                let end_col = display_col(&line, end, self.tab_width);
+                (expand_tabs(&line, self.tab_width), start_col, end_col)
+            }).collect();
+
+            draw_col_separator_no_space(&mut buffer, 1, max_line_num_len + 1, glyphs);
+            for (i, &(ref line, start_col, end_col)) in lines.iter().enumerate() {
+                let row = buffer.num_lines();
+                if i == MAX_SUGGESTION_HIGHLIGHT_LINES {
+                    buffer.puts(row, 0, "...", Style::LineNumber);
+                    break;
+                }
+
+                buffer.puts(row, 0, &(loc.begin.line + i + 1).to_string(), Style::LineNumber);
+                draw_col_separator(&mut buffer, row, max_line_num_len + 1, glyphs);
+                buffer.puts(row, max_line_num_len + 3, line, Style::NoStyle);
+
+                if start_col < end_col {
+                    draw_col_separator(&mut buffer, row + 1, max_line_num_len + 1, glyphs);
+                    draw_range(&mut buffer,
+                               underline,
+                               row + 1,
+                               max_line_num_len + 3 + start_col,
+                               max_line_num_len + 3 + end_col,
+                               style);
+                }
+            }
+        }
 
-        Ok(())
+        buffer.render()
     }
 
     /// Print a group of diagnostic messages.
     ///
     /// The messages within a group are printed atomically without spacing between them, and share
     /// consistent formatting elements, such as aligned line number width.
+    ///
+    /// # Panics
+    ///
+    /// If writing to the output fails. Use `try_emit` to handle the error instead.
     pub fn emit(&mut self, msgs: &[Diagnostic]) {
+        if let Err(e) = self.try_emit(msgs) {
+            panic!("failed to emit error: {}", e);
+        }
+    }
+
+    /// Print a group of diagnostic messages, returning any error from writing to the output.
+    pub fn try_emit(&mut self, msgs: &[Diagnostic]) -> io::Result<()> {
+        for (level, lines) in self.render(msgs) {
+            emit_to_destination(&lines, &level, &self.theme, &mut self.dst)?;
+        }
+
+        let mut dst = self.dst.writable();
+        if !self.short_message {
+            writeln!(dst)?;
+        }
+        dst.flush()
+    }
+
+    /// Print the explanation of an error code from `registry`, returning `false` if the code
+    /// is not registered.
+    ///
+    /// # Panics
+    ///
+    /// If writing to the output fails. Use `try_emit_explanation` to handle the error instead.
+    pub fn emit_explanation(&mut self, registry: &Registry, code: &str) -> bool {
+        match self.try_emit_explanation(registry, code) {
+            Ok(found) => found,
+            Err(e) => panic!("failed to emit error: {}", e),
+        }
+    }
+
+    /// Print the explanation of an error code from `registry`, returning `false` if the code
+    /// is not registered, or any error from writing to the output.
+    pub fn try_emit_explanation(&mut self, registry: &Registry, code: &str) -> io::Result<bool> {
+        match registry.render_explanation(code) {
+            Some(lines) => {
+                emit_to_destination(&lines, &Level::Note, &self.theme, &mut self.dst)?;
+                self.dst.writable().flush()?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Render a group of diagnostic messages to lines of styled text, without printing them.
+    ///
+    /// The output is split into blocks, each paired with the `Level` that determines the color
+    /// of its `Style::UnderlinePrimary` and `Style::LabelPrimary` text. This is the output
+    /// that `emit` would print, except for the blank line after the group.
+    ///
+    /// An emitter used only for rendering can be created with `Emitter::renderer`.
+    pub fn render(&self, msgs: &[Diagnostic]) -> Vec<(Level, Vec<Vec<StyledString>>)> {
         let max_line_num = self.get_max_line_num(msgs) + 1;
         let max_line_num_len = max_line_num.to_string().len();
 
+        let mut output = vec![];
         for msg in msgs {
-            match self.emit_message_default(&msg.spans[..], &vec![(msg.message.clone(), Style::NoStyle)], &msg.code, &msg.level, max_line_num_len, false) {
-                Ok(()) => (),
-                Err(e) => panic!("failed to emit error: {}", e)
+            if self.short_message {
+                output.push((msg.level, self.render_message_short(msg)));
+            } else {
+                self.render_messages_default(msg, max_line_num_len, &mut output);
             }
         }
+        if !self.short_message {
+            self.render_explain_footers(msgs, &mut output);
+        }
+        output
+    }
+}
 
-        let mut dst = self.dst.writable();
-        match write!(dst, "\n") {
-            Err(e) => panic!("failed to emit error: {}", e),
-            _ => {
-                match dst.flush() {
-                    Err(e) => panic!("failed to emit error: {}", e),
-                    _ => (),
-                }
-            }
+impl<'a> Emit for Emitter<'a> {
+    fn emit(&mut self, msgs: &[Diagnostic]) -> io::Result<()> {
+        self.try_emit(msgs)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        true
+    }
+}
+
+
+/// Keep the first `width` columns of styled text.
+fn cut_parts(parts: Vec<(String, Style)>, width: usize) -> Vec<(String, Style)> {
+    let mut col = 0;
+    let mut cut = vec![];
+    for (text, style) in parts {
+        if col >= width {
+            break;
         }
+        let text = slice_columns(&text, 0, width - col);
+        col += str_width(&text);
+        cut.push((text, style));
     }
+    cut
 }
 
+/// Return `style`, or the override if present and the style is `NoStyle`.
+fn style_or_override(style: Style, override_style: Option<Style>) -> Style {
+    if let Some(o) = override_style {
+        if style == Style::NoStyle {
+            return o;
+        }
+    }
+    style
+}
 
-fn draw_col_separator(buffer: &mut StyledBuffer, line: usize, col: usize) {
-    buffer.puts(line, col, "| ", Style::LineNumber);
+fn draw_col_separator(buffer: &mut StyledBuffer, line: usize, col: usize, glyphs: &Glyphs) {
+    buffer.putc(line, col, glyphs.col_separator, Style::LineNumber);
+    buffer.putc(line, col + 1, ' ', Style::LineNumber);
 }
 
-fn draw_col_separator_no_space(buffer: &mut StyledBuffer, line: usize, col: usize) {
-    draw_col_separator_no_space_with_style(buffer, line, col, Style::LineNumber);
+fn draw_col_separator_no_space(buffer: &mut StyledBuffer, line: usize, col: usize, glyphs: &Glyphs) {
+    draw_col_separator_no_space_with_style(buffer, line, col, Style::LineNumber, glyphs);
 }
 
 fn draw_col_separator_no_space_with_style(buffer: &mut StyledBuffer,
                                           line: usize,
                                           col: usize,
-                                          style: Style) {
-    buffer.putc(line, col, '|', style);
+                                          style: Style,
+                                          glyphs: &Glyphs) {
+    buffer.putc(line, col, glyphs.col_separator, style);
 }
 
 fn draw_range(buffer: &mut StyledBuffer, symbol: char, line: usize,
@@ -945,9 +1741,10 @@ fn draw_multiline_line(buffer: &mut StyledBuffer,
                        line: usize,
                        offset: usize,
                        depth: usize,
-                       style: Style)
+                       style: Style,
+                       glyphs: &Glyphs)
 {
-    buffer.putc(line, offset + depth - 1, '|', style);
+    buffer.putc(line, offset + depth - 1, glyphs.vertical, style);
 }
 
 fn num_overlap(a_start: usize, a_end: usize, b_start: usize, b_end:usize, inclusive: bool) -> bool {
@@ -964,8 +1761,9 @@ fn overlaps(a1: &Annotation, a2: &Annotation, padding: usize) -> bool {
     num_overlap(a1.start_col, a1.end_col + padding, a2.start_col, a2.end_col, false)
 }
 
-fn emit_to_destination(rendered_buffer: &Vec<Vec<StyledString>>,
+fn emit_to_destination(rendered_buffer: &[Vec<StyledString>],
                        lvl: &Level,
+                       theme: &Theme,
                        dst: &mut Destination)
                        -> io::Result<()> {
     use lock;
@@ -987,11 +1785,11 @@ fn emit_to_destination(rendered_buffer: &Vec<Vec<StyledString>>,
     let _buffer_lock = lock::acquire_global_lock("rustc_errors");
     for line in rendered_buffer {
         for part in line {
-            dst.apply_style(lvl.clone(), part.style)?;
+            dst.set_color(&theme.style_spec(*lvl, part.style))?;
             write!(dst, "{}", part.text)?;
             dst.reset()?;
         }
-        write!(dst, "\n")?;
+        writeln!(dst)?;
     }
     dst.flush()?;
     Ok(())
@@ -1001,7 +1799,8 @@ fn emit_to_destination(rendered_buffer: &Vec<Vec<StyledString>>,
 enum Destination<'a> {
     Terminal(StandardStream),
     Buffered(BufferWriter),
-    Raw(Box<Write + Send + 'a>),
+    Raw(Box<dyn Write + Send + 'a>),
+    Colored(Box<dyn WriteColor + Send + 'a>),
 }
 
 use self::Destination::*;
@@ -1009,7 +1808,8 @@ use self::Destination::*;
 enum WritableDst<'a, 'b> {
     Terminal(&'b mut StandardStream),
     Buffered(&'b mut BufferWriter, Buffer),
-    Raw(&'b mut Box<Write + Send + 'a>),
+    Raw(&'b mut Box<dyn Write + Send + 'a>),
+    Colored(&'b mut Box<dyn WriteColor + Send + 'a>),
 }
 
 impl<'a> Destination<'a> {
@@ -1036,63 +1836,18 @@ impl<'a> Destination<'a> {
                 WritableDst::Buffered(t, buf)
             }
             Destination::Raw(ref mut t) => WritableDst::Raw(t),
+            Destination::Colored(ref mut t) => WritableDst::Colored(t),
         }
     }
 }
 
 impl<'a, 'b> WritableDst<'a, 'b> {
-    fn apply_style(&mut self, lvl: Level, style: Style) -> io::Result<()> {
-        let mut spec = ColorSpec::new();
-        match style {
-            Style::LineAndColumn => {}
-            Style::LineNumber => {
-                spec.set_bold(true);
-                spec.set_intense(true);
-                if cfg!(windows) {
-                    spec.set_fg(Some(Color::Cyan));
-                } else {
-                    spec.set_fg(Some(Color::Blue));
-                }
-            }
-            Style::Quotation => {}
-            Style::HeaderMsg => {
-                spec.set_bold(true);
-                if cfg!(windows) {
-                    spec.set_intense(true)
-                        .set_fg(Some(Color::White));
-                }
-            }
-            Style::UnderlinePrimary | Style::LabelPrimary => {
-                spec = lvl.color();
-                spec.set_bold(true);
-            }
-            Style::UnderlineSecondary |
-            Style::LabelSecondary => {
-                spec.set_bold(true)
-                    .set_intense(true);
-                if cfg!(windows) {
-                    spec.set_fg(Some(Color::Cyan));
-                } else {
-                    spec.set_fg(Some(Color::Blue));
-                }
-            }
-            Style::NoStyle => {}
-            Style::Level(lvl) => {
-                spec = lvl.color();
-                spec.set_bold(true);
-            }
-            Style::Highlight => {
-                spec.set_bold(true);
-            }
-        }
-        self.set_color(&spec)
-    }
-
     fn set_color(&mut self, color: &ColorSpec) -> io::Result<()> {
         match *self {
             WritableDst::Terminal(ref mut t) => t.set_color(color),
             WritableDst::Buffered(_, ref mut t) => t.set_color(color),
-            WritableDst::Raw(_) => Ok(())
+            WritableDst::Raw(_) => Ok(()),
+            WritableDst::Colored(ref mut t) => t.set_color(color),
         }
     }
 
@@ -1101,6 +1856,7 @@ impl<'a, 'b> WritableDst<'a, 'b> {
             WritableDst::Terminal(ref mut t) => t.reset(),
             WritableDst::Buffered(_, ref mut t) => t.reset(),
             WritableDst::Raw(_) => Ok(()),
+            WritableDst::Colored(ref mut t) => t.reset(),
         }
     }
 }
@@ -1111,25 +1867,28 @@ impl<'a, 'b> Write for WritableDst<'a, 'b> {
             WritableDst::Terminal(ref mut t) => t.write(bytes),
             WritableDst::Buffered(_, ref mut buf) => buf.write(bytes),
             WritableDst::Raw(ref mut w) => w.write(bytes),
+            WritableDst::Colored(ref mut w) => w.write(bytes),
         }
     }
 
     fn flush(&mut self) -> io::Result<()> {
         match *self {
             WritableDst::Terminal(ref mut t) => t.flush(),
-            WritableDst::Buffered(_, ref mut buf) => buf.flush(),
+            WritableDst::Buffered(ref mut dst, ref mut buf) => {
+                dst.print(buf)?;
+                buf.clear();
+                Ok(())
+            }
             WritableDst::Raw(ref mut w) => w.flush(),
+            WritableDst::Colored(ref mut w) => w.flush(),
         }
     }
 }
 
 impl<'a, 'b> Drop for WritableDst<'a, 'b> {
     fn drop(&mut self) {
-        match *self {
-            WritableDst::Buffered(ref mut dst, ref mut buf) => {
-                drop(dst.print(buf));
-            }
-            _ => {}
+        if let WritableDst::Buffered(ref mut dst, ref mut buf) = *self {
+            drop(dst.print(buf));
         }
     }
 }
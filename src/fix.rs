@@ -0,0 +1,149 @@
+//! Applying suggested edits to source files.
+//!
+//! # Example
+//! ```
+//! extern crate codemap;
+//! extern crate codemap_diagnostic;
+//! use codemap::CodeMap;
+//! use codemap_diagnostic::{ Level, Diagnostic, Suggestion, Applicability };
+//! use codemap_diagnostic::fix::apply_suggestions;
+//!
+//! fn main() {
+//!   let mut codemap = CodeMap::new();
+//!   let file_span = codemap.add_file("test.rs".to_owned(), "let x = fo;".to_owned()).span;
+//!
+//!   let d = Diagnostic {
+//!       level: Level::Error,
+//!       message: "cannot find value `fo` in this scope".into(),
+//!       code: Some("C000".to_owned()),
+//!       spans: vec![],
+//!       children: vec![],
+//!       suggestions: vec![Suggestion {
+//!           span: file_span.subspan(8, 10),
+//!           replacement: "foo".to_owned(),
+//!           message: "a local variable with a similar name exists".into(),
+//!           applicability: Applicability::MachineApplicable,
+//!       }],
+//!   };
+//!
+//!   let patches = apply_suggestions(&codemap, &[d], |a| a == Applicability::MachineApplicable)
+//!       .unwrap();
+//!   assert_eq!(patches[0].content, "let x = foo;");
+//! }
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+use codemap::{CodeMap, File, Span};
+use { Applicability, Diagnostic, Suggestion };
+
+/// The contents of a file after applying suggestions to it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FilePatch {
+    /// The file the suggestions apply to.
+    pub file: Arc<File>,
+
+    /// The suggestions that were applied, in source order.
+    pub suggestions: Vec<Suggestion>,
+
+    /// The rewritten source text of the file.
+    pub content: String,
+}
+
+/// Error returned when two suggestions modify the same region of code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OverlapError {
+    /// The file containing both suggestions.
+    pub file: Arc<File>,
+
+    /// The span of the suggestion that starts first in the file.
+    pub first: Span,
+
+    /// The span of the suggestion overlapping with `first`.
+    pub second: Span,
+}
+
+impl fmt::Display for OverlapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let first = self.file.find_line_col(self.first.low());
+        let second = self.file.find_line_col(self.second.low());
+        write!(f, "overlapping suggestions in {} at {}:{} and {}:{}",
+               self.file.name(),
+               first.line + 1, first.column + 1,
+               second.line + 1, second.column + 1)
+    }
+}
+
+impl Error for OverlapError {}
+
+/// Apply the suggestions of `diagnostics` whose applicability is accepted by `filter`.
+///
+/// Returns the rewritten contents of each modified file, in the order the files are first
+/// referenced by the suggestions. Identical edits are only applied once, and any other
+/// suggestions that modify the same region of code result in an `OverlapError`.
+pub fn apply_suggestions<F>(code_map: &CodeMap,
+                            diagnostics: &[Diagnostic],
+                            filter: F)
+                            -> Result<Vec<FilePatch>, OverlapError>
+    where F: Fn(Applicability) -> bool
+{
+    let mut patches: Vec<FilePatch> = vec![];
+
+    let suggestions = diagnostics.iter()
+        .flat_map(|d| d.suggestions.iter())
+        .filter(|s| filter(s.applicability));
+
+    for suggestion in suggestions {
+        let file = code_map.find_file(suggestion.span.low());
+        match patches.iter_mut().find(|p| p.file == *file) {
+            Some(patch) => {
+                let duplicate = patch.suggestions.iter().any(|s| {
+                    s.span == suggestion.span && s.replacement == suggestion.replacement
+                });
+                if !duplicate {
+                    patch.suggestions.push(suggestion.clone());
+                }
+            }
+            None => {
+                patches.push(FilePatch {
+                    file: file.clone(),
+                    suggestions: vec![suggestion.clone()],
+                    content: String::new(),
+                });
+            }
+        }
+    }
+
+    for patch in &mut patches {
+        patch.suggestions.sort_by_key(|s| (s.span.low(), s.span.high()));
+
+        for pair in patch.suggestions.windows(2) {
+            let (a, b) = (&pair[0].span, &pair[1].span);
+            // Two insertions at the same position are ambiguous, because either could go first.
+            if b.low() < a.high() || (a.low() == b.low() && a.len() == 0 && b.len() == 0) {
+                return Err(OverlapError {
+                    file: patch.file.clone(),
+                    first: *a,
+                    second: *b,
+                });
+            }
+        }
+
+        let source = patch.file.source();
+        let file_start = patch.file.span.low();
+        let mut content = String::with_capacity(source.len());
+        let mut pos = 0;
+        for suggestion in &patch.suggestions {
+            let low = (suggestion.span.low() - file_start) as usize;
+            let high = (suggestion.span.high() - file_start) as usize;
+            content.push_str(&source[pos..low]);
+            content.push_str(&suggestion.replacement);
+            pos = high;
+        }
+        content.push_str(&source[pos..]);
+        patch.content = content;
+    }
+
+    Ok(patches)
+}
@@ -0,0 +1,232 @@
+//! Bookkeeping for the diagnostics reported by a tool.
+
+use std::collections::HashSet;
+use std::io;
+use { Diagnostic, Emit, Level, LintPolicy };
+
+/// Emits diagnostic messages while counting them by level.
+///
+/// A `Handler` can stop emitting after a number of errors, and prints a summary such as
+/// "aborting due to 3 previous errors; 2 warnings emitted" when finished. The summary and the
+/// note printed when stopping are only added to human-readable output, as reported by
+/// `Emit::is_human_readable`.
+pub struct Handler<'a> {
+    emitter: Box<dyn Emit + 'a>,
+    counts: [usize; 5],
+    error_limit: Option<usize>,
+    lint_policy: LintPolicy,
+    dedup: bool,
+    emitted: HashSet<Diagnostic>,
+    stopped: bool,
+}
+
+fn level_index(level: Level) -> usize {
+    match level {
+        Level::Bug => 0,
+        Level::Error => 1,
+        Level::Warning => 2,
+        Level::Note => 3,
+        Level::Help => 4,
+    }
+}
+
+fn plural(count: usize, word: &str) -> String {
+    if count == 1 {
+        format!("{} {}", count, word)
+    } else {
+        format!("{} {}s", count, word)
+    }
+}
+
+impl<'a> Handler<'a> {
+    /// Creates a handler emitting to any output format.
+    pub fn new(emitter: Box<dyn Emit + 'a>) -> Handler<'a> {
+        Handler {
+            emitter,
+            counts: [0; 5],
+            error_limit: None,
+            lint_policy: LintPolicy::new(),
+            dedup: false,
+            emitted: HashSet::new(),
+            stopped: false,
+        }
+    }
+
+    /// Stop emitting diagnostics after this many errors, or never if `None`. The default is
+    /// `None`.
+    ///
+    /// The limit is checked before each group, so a group is always emitted whole. The first
+    /// group discarded is replaced with a note saying that the output stops there.
+    pub fn error_limit(mut self, error_limit: Option<usize>) -> Handler<'a> {
+        self.error_limit = error_limit;
+        self
+    }
+
+    /// Set the policy used to allow warnings or promote them to errors before they are emitted
+    /// and counted.
+    pub fn lint_policy(mut self, lint_policy: LintPolicy) -> Handler<'a> {
+        self.lint_policy = lint_policy;
+        self
+    }
+
+    /// Skip diagnostics identical to one already emitted by this handler. The default is
+    /// `false`.
+    ///
+    /// Skipped diagnostics are not counted.
+    pub fn dedup(mut self, dedup: bool) -> Handler<'a> {
+        self.dedup = dedup;
+        self
+    }
+
+    /// The number of diagnostics emitted with a level.
+    pub fn count(&self, level: Level) -> usize {
+        self.counts[level_index(level)]
+    }
+
+    /// The number of errors emitted, including internal compiler errors.
+    pub fn error_count(&self) -> usize {
+        self.count(Level::Bug) + self.count(Level::Error)
+    }
+
+    /// The number of warnings emitted.
+    pub fn warning_count(&self) -> usize {
+        self.count(Level::Warning)
+    }
+
+    /// Whether any errors have been emitted.
+    pub fn has_errors(&self) -> bool {
+        self.error_count() > 0
+    }
+
+    /// Whether the error limit has been reached, so further diagnostics are discarded.
+    pub fn error_limit_reached(&self) -> bool {
+        self.error_limit.is_some_and(|limit| self.error_count() >= limit)
+    }
+
+    /// Print a group of diagnostic messages, unless the error limit has been reached.
+    ///
+    /// # Panics
+    ///
+    /// If writing to the output fails. Use `try_emit` to handle the error instead.
+    pub fn emit(&mut self, msgs: &[Diagnostic]) {
+        if let Err(e) = self.try_emit(msgs) {
+            panic!("failed to emit error: {}", e);
+        }
+    }
+
+    /// Print a group of diagnostic messages, returning any error from writing to the output.
+    pub fn try_emit(&mut self, msgs: &[Diagnostic]) -> io::Result<()> {
+        if self.error_limit_reached() {
+            if self.stopped || msgs.is_empty() {
+                return Ok(());
+            }
+            self.stopped = true;
+            if !self.emitter.is_human_readable() {
+                return Ok(());
+            }
+            let notice = Diagnostic {
+                level: Level::Note,
+                message: "too many errors, stopping".into(),
+                code: None,
+                spans: vec![],
+                children: vec![],
+                suggestions: vec![],
+            };
+            return self.emitter.emit(&[notice]);
+        }
+
+        let mut emitted = vec![];
+        for msg in msgs {
+            if self.dedup {
+                if self.emitted.contains(msg) {
+                    continue;
+                }
+                self.emitted.insert(msg.clone());
+            }
+            if let Some(msg) = self.lint_policy.apply(msg) {
+                self.counts[level_index(msg.level)] += 1;
+                emitted.push(msg);
+            }
+        }
+
+        if emitted.is_empty() {
+            Ok(())
+        } else {
+            self.emitter.emit(&emitted)
+        }
+    }
+
+    /// The summary of the errors and warnings emitted, if there were any.
+    pub fn summary(&self) -> Option<Diagnostic> {
+        let errors = self.error_count();
+        let warnings = self.warning_count();
+
+        let (level, message) = if errors > 0 {
+            let mut message = if errors == 1 {
+                "aborting due to previous error".to_owned()
+            } else {
+                format!("aborting due to {} previous errors", errors)
+            };
+            if warnings > 0 {
+                message.push_str(&format!("; {} emitted", plural(warnings, "warning")));
+            }
+            (Level::Error, message)
+        } else if warnings > 0 {
+            (Level::Warning, format!("{} emitted", plural(warnings, "warning")))
+        } else {
+            return None;
+        };
+
+        Some(Diagnostic {
+            level,
+            message: message.into(),
+            code: None,
+            spans: vec![],
+            children: vec![],
+            suggestions: vec![],
+        })
+    }
+
+    /// Print the summary, if the output is human-readable, and complete the output.
+    ///
+    /// # Panics
+    ///
+    /// If writing to the output fails. Use `try_finish` to handle the error instead.
+    pub fn finish(&mut self) {
+        if let Err(e) = self.try_finish() {
+            panic!("failed to emit error: {}", e);
+        }
+    }
+
+    /// Print the summary, if the output is human-readable, and complete the output, returning
+    /// any error from writing to the output.
+    ///
+    /// The output is flushed first, so the summary follows any buffered messages.
+    pub fn try_finish(&mut self) -> io::Result<()> {
+        self.emitter.flush()?;
+        if self.emitter.is_human_readable() {
+            if let Some(summary) = self.summary() {
+                self.emitter.emit(&[summary])?;
+            }
+        }
+        self.emitter.finish()
+    }
+}
+
+impl<'a> Emit for Handler<'a> {
+    fn emit(&mut self, msgs: &[Diagnostic]) -> io::Result<()> {
+        self.try_emit(msgs)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.emitter.flush()
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.emitter.is_human_readable()
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.try_finish()
+    }
+}
@@ -0,0 +1,33 @@
+//! Syntax highlighting of quoted source code.
+
+use std::ops::Range;
+use codemap::File;
+
+/// The syntactic category of a piece of source code, used to choose its color.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SyntaxClass {
+    /// Keywords and reserved words.
+    Keyword,
+    /// Names of types.
+    Type,
+    /// Names of functions.
+    Function,
+    /// String and character literals.
+    String,
+    /// Numeric literals.
+    Number,
+    /// Comments.
+    Comment,
+    /// Operators.
+    Operator,
+}
+
+/// Colorizes the source code quoted in diagnostic messages.
+///
+/// Highlighting applies only to the quoted source text; underlines, labels and the left margin
+/// keep their usual colors.
+pub trait Highlighter {
+    /// Returns the highlighted parts of line `line` (0-based) of `file`, as byte ranges within
+    /// `file.source_line(line)`. Parts of the line not covered by any range are left unstyled.
+    fn highlight_line(&self, file: &File, line: usize) -> Vec<(Range<usize>, SyntaxClass)>;
+}
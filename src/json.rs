@@ -0,0 +1,251 @@
+//! Machine-readable output compatible with rustc's `--error-format=json`.
+
+use std::fmt;
+use std::io::prelude::*;
+use std::io;
+use codemap::{CodeMap, Span};
+use { Applicability, Diagnostic, Emit, SpanLabel, SpanStyle, Suggestion };
+use emitter::Emitter;
+
+/// Formats and prints diagnostic messages as JSON, one object per line.
+pub struct JsonEmitter<'a> {
+    dst: Box<dyn Write + Send + 'a>,
+    cm: Option<&'a CodeMap>,
+}
+
+impl<'a> JsonEmitter<'a> {
+    /// Creates a JSON emitter wrapping stderr.
+    pub fn stderr(code_map: Option<&'a CodeMap>) -> JsonEmitter<'a> {
+        JsonEmitter::new(Box::new(io::stderr()), code_map)
+    }
+
+    /// Creates a JSON emitter wrapping a vector.
+    pub fn vec(vec: &'a mut Vec<u8>, code_map: Option<&'a CodeMap>) -> JsonEmitter<'a> {
+        JsonEmitter::new(Box::new(vec), code_map)
+    }
+
+    /// Creates a JSON emitter wrapping a boxed `Write` trait object.
+    pub fn new(dst: Box<dyn Write + Send + 'a>, code_map: Option<&'a CodeMap>) -> JsonEmitter<'a> {
+        JsonEmitter {
+            dst,
+            cm: code_map,
+        }
+    }
+
+    fn spans_to_json(&self, spans: &[SpanLabel]) -> Json {
+        Json::Array(spans.iter().filter_map(|span_label| {
+            self.span_to_json(span_label.span,
+                              span_label.style == SpanStyle::Primary,
+                              span_label.label.as_ref().map(|s| &s[..]),
+                              None)
+        }).collect())
+    }
+
+    fn span_to_json(&self,
+                    span: Span,
+                    is_primary: bool,
+                    label: Option<&str>,
+                    suggestion: Option<&Suggestion>)
+                    -> Option<Json> {
+        let cm = self.cm?;
+        let loc = cm.look_up_span(span);
+        let file_start = loc.file.span.low();
+
+        let text = (loc.begin.line..loc.end.line + 1).map(|line| {
+            let source = loc.file.source_line(line);
+            let highlight_start = if line == loc.begin.line { loc.begin.column } else { 0 };
+            let highlight_end = if line == loc.end.line {
+                loc.end.column
+            } else {
+                source.chars().count()
+            };
+            Json::Object(vec![
+                ("text", source.into()),
+                ("highlight_start", (highlight_start + 1).into()),
+                ("highlight_end", (highlight_end + 1).into()),
+            ])
+        }).collect();
+
+        Some(Json::Object(vec![
+            ("file_name", loc.file.name().into()),
+            ("byte_start", ((span.low() - file_start) as usize).into()),
+            ("byte_end", ((span.high() - file_start) as usize).into()),
+            ("line_start", (loc.begin.line + 1).into()),
+            ("line_end", (loc.end.line + 1).into()),
+            ("column_start", (loc.begin.column + 1).into()),
+            ("column_end", (loc.end.column + 1).into()),
+            ("is_primary", is_primary.into()),
+            ("text", Json::Array(text)),
+            ("label", label.into()),
+            ("suggested_replacement", suggestion.map(|s| &s.replacement[..]).into()),
+            ("suggestion_applicability",
+             suggestion.map(|s| applicability_to_str(s.applicability)).into()),
+            ("expansion", Json::Null),
+        ]))
+    }
+
+    fn diagnostic_to_json(&self, diagnostic: &Diagnostic) -> Json {
+        let children = diagnostic.children.iter().map(|child| {
+            Json::Object(vec![
+                ("message", Json::String(child.message.to_string())),
+                ("code", Json::Null),
+                ("level", child.level.to_str().into()),
+                ("spans", self.spans_to_json(&child.spans)),
+                ("children", Json::Array(vec![])),
+                ("rendered", Json::Null),
+            ])
+        });
+
+        let suggestions = diagnostic.suggestions.iter().map(|suggestion| {
+            let spans = self.span_to_json(suggestion.span, true, None, Some(suggestion));
+            Json::Object(vec![
+                ("message", Json::String(suggestion.message.to_string())),
+                ("code", Json::Null),
+                ("level", "help".into()),
+                ("spans", Json::Array(spans.into_iter().collect())),
+                ("children", Json::Array(vec![])),
+                ("rendered", Json::Null),
+            ])
+        });
+
+        let code = diagnostic.code.as_ref().map(|code| {
+            Json::Object(vec![
+                ("code", (&code[..]).into()),
+                ("explanation", Json::Null),
+            ])
+        });
+
+        let mut rendered = Vec::new();
+        Emitter::vec(&mut rendered, self.cm).emit(::std::slice::from_ref(diagnostic));
+
+        Json::Object(vec![
+            ("$message_type", "diagnostic".into()),
+            ("message", Json::String(diagnostic.message.to_string())),
+            ("code", code.into()),
+            ("level", diagnostic.level.to_str().into()),
+            ("spans", self.spans_to_json(&diagnostic.spans)),
+            ("children", Json::Array(children.chain(suggestions).collect())),
+            ("rendered", (&String::from_utf8_lossy(&rendered)[..]).into()),
+        ])
+    }
+
+    /// Print a group of diagnostic messages.
+    ///
+    /// Each diagnostic is written as a JSON object on its own line.
+    ///
+    /// # Panics
+    ///
+    /// If writing to the output fails. Use `try_emit` to handle the error instead.
+    pub fn emit(&mut self, msgs: &[Diagnostic]) {
+        if let Err(e) = self.try_emit(msgs) {
+            panic!("failed to emit error: {}", e);
+        }
+    }
+
+    /// Print a group of diagnostic messages, returning any error from writing to the output.
+    pub fn try_emit(&mut self, msgs: &[Diagnostic]) -> io::Result<()> {
+        for msg in msgs {
+            let json = self.diagnostic_to_json(msg);
+            writeln!(self.dst, "{}", json)?;
+        }
+        self.dst.flush()
+    }
+}
+
+impl<'a> Emit for JsonEmitter<'a> {
+    fn emit(&mut self, msgs: &[Diagnostic]) -> io::Result<()> {
+        self.try_emit(msgs)
+    }
+}
+
+fn applicability_to_str(applicability: Applicability) -> &'static str {
+    match applicability {
+        Applicability::MachineApplicable => "MachineApplicable",
+        Applicability::MaybeIncorrect => "MaybeIncorrect",
+        Applicability::HasPlaceholders => "HasPlaceholders",
+        Applicability::Unspecified => "Unspecified",
+    }
+}
+
+/// A JSON value, written in compact form by its `Display` implementation.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(usize),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl<'a> From<&'a str> for Json {
+    fn from(s: &'a str) -> Json {
+        Json::String(s.to_owned())
+    }
+}
+
+impl From<usize> for Json {
+    fn from(n: usize) -> Json {
+        Json::Number(n)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(b: bool) -> Json {
+        Json::Bool(b)
+    }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(o: Option<T>) -> Json {
+        o.map_or(Json::Null, Into::into)
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) => write!(f, "{}", n),
+            Json::String(ref s) => write_json_string(f, s),
+            Json::Array(ref items) => {
+                f.write_str("[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                f.write_str("]")
+            }
+            Json::Object(ref fields) => {
+                f.write_str("{")?;
+                for (i, &(key, ref value)) in fields.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str(",")?;
+                    }
+                    write_json_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+fn write_json_string(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    f.write_str("\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    f.write_str("\"")
+}
@@ -22,9 +22,11 @@
 //!   };
 //!   let d = Diagnostic {
 //!       level: Level::Error,
-//!       message: "cannot find value `foo` in this scope".to_owned(),
+//!       message: "cannot find value `foo` in this scope".into(),
 //!       code: Some("C000".to_owned()),
-//!       spans: vec![label]
+//!       spans: vec![label],
+//!       children: vec![],
+//!       suggestions: vec![],
 //!   };
 //!
 //!   let mut emitter = Emitter::stderr(ColorConfig::Always, Some(&codemap));
@@ -32,39 +34,202 @@
 //! }
 //! ```
 
-extern crate termcolor;
+pub extern crate termcolor;
 extern crate codemap;
+extern crate unicode_width;
+extern crate terminal_size;
 
+use std::io;
 use codemap::Span;
 
 mod lock;
 mod snippet;
 mod styled_buffer;
 mod emitter;
+mod theme;
+mod highlight;
+mod json;
+mod sarif;
+mod handler;
+mod lint;
+mod deferred;
+mod registry;
+pub mod fix;
 
-pub use emitter::{ ColorConfig, Emitter };
-use termcolor::{ ColorSpec, Color };
+pub use emitter::{ Charset, ColorConfig, Emitter, MultilineFold };
+pub use snippet::{ Style, StyledString };
+pub use theme::Theme;
+pub use highlight::{ Highlighter, SyntaxClass };
+pub use json::JsonEmitter;
+pub use sarif::SarifEmitter;
+pub use handler::Handler;
+pub use lint::{ LintLevel, LintPolicy };
+pub use deferred::DeferredEmitter;
+pub use registry::Registry;
+
+/// An output format for diagnostic messages.
+///
+/// This allows the format to be chosen at runtime, by using a `Box<dyn Emit>`.
+///
+/// `Emitter`, `JsonEmitter` and `SarifEmitter` also have inherent `emit` methods, and
+/// `SarifEmitter` an inherent `finish` method, which panic if writing fails instead of returning
+/// the error. Method calls on those types resolve to the inherent methods, so use
+/// `Emit::emit(&mut emitter, msgs)` to call the trait method on them directly.
+pub trait Emit {
+    /// Print a group of diagnostic messages.
+    ///
+    /// The messages within a group are printed atomically, and share consistent formatting
+    /// elements where the format supports it.
+    fn emit(&mut self, msgs: &[Diagnostic]) -> io::Result<()>;
+
+    /// Print any messages buffered by the format.
+    ///
+    /// The default implementation does nothing.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Whether the output is meant to be read by people rather than by tools.
+    ///
+    /// `Handler` only adds its own messages, like the "aborting due to previous error" summary,
+    /// to human-readable output. The default implementation returns `false`.
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    /// Complete the output after the last group of messages.
+    ///
+    /// Formats that write a single document for all messages, like SARIF, write it here. The
+    /// default implementation does nothing.
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Emitting through a reference, so that an output format like `Handler` can be used by a
+/// `DeferredEmitter` while it is still reachable by the caller.
+impl<E: Emit + ?Sized> Emit for &mut E {
+    fn emit(&mut self, msgs: &[Diagnostic]) -> io::Result<()> {
+        (**self).emit(msgs)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (**self).flush()
+    }
+
+    fn is_human_readable(&self) -> bool {
+        (**self).is_human_readable()
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        (**self).finish()
+    }
+}
 
 /// A diagnostic message.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Diagnostic {
     /// The severity of the message, used to set color scheme
     pub level: Level,
 
     /// Message used as the headline of the error
-    pub message: String,
+    pub message: Message,
 
     /// A short error number or code
     pub code: Option<String>,
 
     /// Locations to underline in the code
     pub spans: Vec<SpanLabel>,
+
+    /// Notes and help messages displayed below the main message
+    pub children: Vec<SubDiagnostic>,
+
+    /// Suggested changes to the code, displayed after the children
+    pub suggestions: Vec<Suggestion>,
+}
+
+/// A secondary message attached to a Diagnostic, such as a note or help message.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SubDiagnostic {
+    /// The severity of the message, used to set color scheme
+    pub level: Level,
+
+    /// Message displayed after the level
+    pub message: Message,
+
+    /// Locations to underline in the code, if any
+    pub spans: Vec<SpanLabel>,
+}
+
+/// The text of a message, made of fragments that can be styled individually.
+///
+/// Output formats without styling, such as JSON, use the concatenated text, which is also what
+/// `Display` produces.
+///
+/// ```
+/// use codemap_diagnostic::Message;
+///
+/// let message = Message::new()
+///     .normal("expected ")
+///     .highlighted("`u32`")
+///     .normal(", found ")
+///     .highlighted("`&str`");
+/// assert_eq!(message.to_string(), "expected `u32`, found `&str`");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Message {
+    /// The fragments of the message, in order.
+    pub parts: Vec<(String, Style)>,
+}
+
+impl Message {
+    /// Create an empty message.
+    pub fn new() -> Message {
+        Message { parts: vec![] }
+    }
+
+    /// Append unstyled text.
+    pub fn normal(self, text: &str) -> Message {
+        self.styled(text, Style::NoStyle)
+    }
+
+    /// Append text emphasized with `Style::Highlight`.
+    pub fn highlighted(self, text: &str) -> Message {
+        self.styled(text, Style::Highlight)
+    }
+
+    /// Append text with the given style.
+    pub fn styled(mut self, text: &str, style: Style) -> Message {
+        self.parts.push((text.to_owned(), style));
+        self
+    }
+}
+
+impl From<String> for Message {
+    fn from(text: String) -> Message {
+        Message { parts: vec![(text, Style::NoStyle)] }
+    }
+}
+
+impl<'a> From<&'a str> for Message {
+    fn from(text: &'a str) -> Message {
+        Message::from(text.to_owned())
+    }
+}
+
+impl ::std::fmt::Display for Message {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        for (text, _) in &self.parts {
+            f.write_str(text)?;
+        }
+        Ok(())
+    }
 }
 
 /// A level representing the severity of a Diagnostic.
 ///
 /// These result in different output styling.
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
 pub enum Level {
     Bug,
     Error,
@@ -80,30 +245,6 @@ impl ::std::fmt::Display for Level {
 }
 
 impl Level {
-    fn color(self) -> ColorSpec {
-        let mut spec = ColorSpec::new();
-        use self::Level::*;
-        match self {
-            Bug | Error => {
-                spec.set_fg(Some(Color::Red))
-                    .set_intense(true);
-            }
-            Warning => {
-                spec.set_fg(Some(Color::Yellow))
-                    .set_intense(cfg!(windows));
-            }
-            Note => {
-                spec.set_fg(Some(Color::Green))
-                    .set_intense(true);
-            }
-            Help => {
-                spec.set_fg(Some(Color::Cyan))
-                    .set_intense(true);
-            }
-        }
-        spec
-    }
-
     pub fn to_str(self) -> &'static str {
         use self::Level::*;
 
@@ -118,7 +259,7 @@ impl Level {
 }
 
 /// A labeled region of the code related to a Diagnostic.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct SpanLabel {
     /// The location in the code.
     ///
@@ -133,8 +274,42 @@ pub struct SpanLabel {
 }
 
 /// Underline style for a SpanLabel.
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
 pub enum SpanStyle {
     Primary,
     Secondary,
 }
+
+/// A suggested edit to the code, displayed as a `help` message showing the modified source.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Suggestion {
+    /// The region of code to replace. An empty span inserts the replacement at that position.
+    ///
+    /// This Span must come from the same CodeMap used to construct the Emitter.
+    pub span: Span,
+
+    /// The text to put in place of the span. An empty string deletes the span.
+    pub replacement: String,
+
+    /// Message displayed in the header of the suggestion
+    pub message: Message,
+
+    /// How confident the tool is that the suggestion is correct.
+    pub applicability: Applicability,
+}
+
+/// Indicates the confidence in the correctness of a Suggestion.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
+pub enum Applicability {
+    /// The suggestion is definitely what the user intended, and can be applied automatically.
+    MachineApplicable,
+
+    /// The suggestion may be what the user intended, but it is uncertain.
+    MaybeIncorrect,
+
+    /// The suggestion contains placeholders like `(...)` that the user must fill in.
+    HasPlaceholders,
+
+    /// The applicability of the suggestion is unknown.
+    Unspecified,
+}
@@ -0,0 +1,125 @@
+//! Changing the level of warnings by their code, like rustc's `-A`, `-W`, `-D` and `-F` flags.
+
+use { Diagnostic, Level, SubDiagnostic };
+
+/// The name of the group containing all warnings.
+pub const WARNINGS: &str = "warnings";
+
+/// How warnings with a code are reported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LintLevel {
+    /// Discard the warnings.
+    Allow,
+
+    /// Report the warnings as warnings.
+    Warn,
+
+    /// Report the warnings as errors.
+    Deny,
+
+    /// Report the warnings as errors, and ignore later attempts to change the level.
+    ///
+    /// Forbidding `warnings` also overrides `Allow` and `Warn` set for individual codes.
+    Forbid,
+}
+
+impl LintLevel {
+    /// The command line flag that sets this level.
+    pub fn flag(self) -> &'static str {
+        match self {
+            LintLevel::Allow => "-A",
+            LintLevel::Warn => "-W",
+            LintLevel::Deny => "-D",
+            LintLevel::Forbid => "-F",
+        }
+    }
+}
+
+/// Levels for warnings, chosen by their code.
+///
+/// Only diagnostics with `Level::Warning` are affected. The level set for the code `warnings`
+/// applies to all warnings that would otherwise be reported as warnings.
+///
+/// ```
+/// use codemap_diagnostic::{ LintLevel, LintPolicy };
+///
+/// // -A W000 -D W012 -D warnings
+/// let policy = LintPolicy::new()
+///     .set("W000", LintLevel::Allow)
+///     .set("W012", LintLevel::Deny)
+///     .set("warnings", LintLevel::Deny);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LintPolicy {
+    levels: Vec<(String, LintLevel)>,
+}
+
+impl LintPolicy {
+    /// Creates a policy reporting all warnings as warnings.
+    pub fn new() -> LintPolicy {
+        LintPolicy { levels: vec![] }
+    }
+
+    /// Set the level of warnings with `code`, or of all warnings if `code` is `warnings`.
+    ///
+    /// A later call for the same code replaces the level, unless it was `LintLevel::Forbid`.
+    pub fn set(mut self, code: &str, level: LintLevel) -> LintPolicy {
+        match self.levels.iter_mut().find(|(c, _)| c == code) {
+            Some(&mut (_, LintLevel::Forbid)) => {}
+            Some(entry) => entry.1 = level,
+            None => self.levels.push((code.to_owned(), level)),
+        }
+        self
+    }
+
+    /// The level set for `code`, if any.
+    pub fn get(&self, code: &str) -> Option<LintLevel> {
+        self.levels.iter().find(|(c, _)| c == code).map(|&(_, level)| level)
+    }
+
+    /// Apply the policy to a diagnostic.
+    ///
+    /// Returns `None` if the diagnostic is allowed. A warning promoted to an error gets a note
+    /// explaining which setting caused it.
+    pub fn apply(&self, diagnostic: &Diagnostic) -> Option<Diagnostic> {
+        if diagnostic.level != Level::Warning {
+            return Some(diagnostic.clone());
+        }
+
+        let forbidden = self.get(WARNINGS) == Some(LintLevel::Forbid);
+        let code = diagnostic.code.as_ref().map(|c| &c[..]);
+        let (level, note) = match code.and_then(|code| self.get(code).map(|level| (code, level))) {
+            Some((code, level)) if level != LintLevel::Warn &&
+                                   !(forbidden && level == LintLevel::Allow) => {
+                (level, format!("requested on the command line with `{} {}`", level.flag(), code))
+            }
+            _ => match self.get(WARNINGS) {
+                Some(level) => {
+                    let note = match code {
+                        Some(code) => format!("`{} {}` implied by `{} {}`",
+                                              level.flag(), code, level.flag(), WARNINGS),
+                        None => format!("requested on the command line with `{} {}`",
+                                        level.flag(), WARNINGS),
+                    };
+                    (level, note)
+                }
+                None => (LintLevel::Warn, String::new()),
+            },
+        };
+
+        match level {
+            LintLevel::Allow => None,
+            LintLevel::Warn => Some(diagnostic.clone()),
+            LintLevel::Deny | LintLevel::Forbid => {
+                let mut diagnostic = diagnostic.clone();
+                diagnostic.level = Level::Error;
+                diagnostic.children.insert(0, SubDiagnostic {
+                    level: Level::Note,
+                    message: note.into(),
+                    spans: vec![],
+                });
+                Some(diagnostic)
+            }
+        }
+    }
+}
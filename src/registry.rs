@@ -0,0 +1,66 @@
+//! Long-form explanations of error codes, like rustc's `--explain`.
+
+use std::collections::HashMap;
+use snippet::{Style, StyledString};
+use styled_buffer::split_backticks;
+
+/// A set of error codes and their explanations, written in Markdown.
+///
+/// ```
+/// use codemap_diagnostic::Registry;
+///
+/// let registry = Registry::new()
+///     .add("C000", "## Unresolved name\n\nA name was used that is not in scope.\n");
+/// assert!(registry.find_explanation("C000").is_some());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Registry {
+    explanations: HashMap<String, String>,
+}
+
+impl Registry {
+    /// Creates an empty registry.
+    pub fn new() -> Registry {
+        Registry { explanations: HashMap::new() }
+    }
+
+    /// Add the explanation of an error code.
+    pub fn add(mut self, code: &str, explanation: &str) -> Registry {
+        self.explanations.insert(code.to_owned(), explanation.to_owned());
+        self
+    }
+
+    /// The explanation of an error code, if it is registered.
+    pub fn find_explanation(&self, code: &str) -> Option<&str> {
+        self.explanations.get(code).map(|e| &e[..])
+    }
+
+    /// Render the explanation of an error code as lines of styled text, or `None` if it is not
+    /// registered.
+    ///
+    /// Headings are drawn with `Style::Heading`. Fenced code blocks are indented and drawn with
+    /// `Style::Code`, as is inline code between backticks.
+    pub fn render_explanation(&self, code: &str) -> Option<Vec<Vec<StyledString>>> {
+        let explanation = self.find_explanation(code)?;
+
+        let mut lines = vec![];
+        let mut in_code_block = false;
+        for line in explanation.lines() {
+            let parts = if line.trim_start().starts_with("```") {
+                in_code_block = !in_code_block;
+                continue;
+            } else if in_code_block {
+                vec![(format!("    {}", line), Style::Code)]
+            } else if line.starts_with('#') {
+                vec![(line.trim_start_matches('#').trim_start().to_owned(), Style::Heading)]
+            } else {
+                split_backticks(line, Style::NoStyle, Style::Code)
+            };
+
+            lines.push(parts.into_iter()
+                .map(|(text, style)| StyledString { text, style })
+                .collect());
+        }
+        Some(lines)
+    }
+}
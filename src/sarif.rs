@@ -0,0 +1,188 @@
+//! Static analysis reports in the SARIF 2.1.0 format.
+
+use std::io::prelude::*;
+use std::io;
+use codemap::{CodeMap, Span};
+use { Diagnostic, Emit, Level, SpanStyle };
+use json::Json;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Collects diagnostic messages and writes them as a single SARIF log.
+///
+/// Diagnostics passed to `emit` are buffered until `finish` is called.
+pub struct SarifEmitter<'a> {
+    dst: Box<dyn Write + Send + 'a>,
+    cm: Option<&'a CodeMap>,
+    tool_name: String,
+    rules: Vec<String>,
+    results: Vec<Json>,
+}
+
+impl<'a> SarifEmitter<'a> {
+    /// Creates a SARIF emitter wrapping a vector.
+    ///
+    /// `tool_name` is reported as the name of the analysis tool that produced the results.
+    pub fn vec(vec: &'a mut Vec<u8>, tool_name: &str, code_map: Option<&'a CodeMap>) -> SarifEmitter<'a> {
+        SarifEmitter::new(Box::new(vec), tool_name, code_map)
+    }
+
+    /// Creates a SARIF emitter wrapping a boxed `Write` trait object.
+    ///
+    /// `tool_name` is reported as the name of the analysis tool that produced the results.
+    pub fn new(dst: Box<dyn Write + Send + 'a>,
+               tool_name: &str,
+               code_map: Option<&'a CodeMap>)
+               -> SarifEmitter<'a> {
+        SarifEmitter {
+            dst,
+            cm: code_map,
+            tool_name: tool_name.to_owned(),
+            rules: vec![],
+            results: vec![],
+        }
+    }
+
+    fn location_to_json(&self, span: Span, label: Option<&str>, id: Option<usize>) -> Option<Json> {
+        let cm = self.cm?;
+        let loc = cm.look_up_span(span);
+
+        let region = Json::Object(vec![
+            ("startLine", (loc.begin.line + 1).into()),
+            ("startColumn", (loc.begin.column + 1).into()),
+            ("endLine", (loc.end.line + 1).into()),
+            ("endColumn", (loc.end.column + 1).into()),
+        ]);
+
+        let uri = Json::String(file_uri(loc.file.name()));
+        let physical_location = Json::Object(vec![
+            ("artifactLocation", Json::Object(vec![("uri", uri)])),
+            ("region", region),
+        ]);
+
+        let mut fields = vec![];
+        if let Some(id) = id {
+            fields.push(("id", id.into()));
+        }
+        fields.push(("physicalLocation", physical_location));
+        if let Some(label) = label {
+            fields.push(("message", Json::Object(vec![("text", label.into())])));
+        }
+        Some(Json::Object(fields))
+    }
+
+    fn diagnostic_to_json(&self, diagnostic: &Diagnostic) -> Json {
+        let level = match diagnostic.level {
+            Level::Bug | Level::Error => "error",
+            Level::Warning => "warning",
+            Level::Note | Level::Help => "note",
+        };
+
+        let locations = diagnostic.spans.iter()
+            .filter(|s| s.style == SpanStyle::Primary)
+            .filter_map(|s| self.location_to_json(s.span, s.label.as_ref().map(|l| &l[..]), None))
+            .collect();
+
+        let related_locations = diagnostic.spans.iter()
+            .filter(|s| s.style == SpanStyle::Secondary)
+            .enumerate()
+            .filter_map(|(i, s)| {
+                self.location_to_json(s.span, s.label.as_ref().map(|l| &l[..]), Some(i))
+            })
+            .collect();
+
+        let mut fields = vec![];
+        if let Some(ref code) = diagnostic.code {
+            fields.push(("ruleId", (&code[..]).into()));
+        }
+        fields.push(("level", level.into()));
+        fields.push(("message", Json::Object(vec![("text", Json::String(diagnostic.message.to_string()))])));
+        fields.push(("locations", Json::Array(locations)));
+        fields.push(("relatedLocations", Json::Array(related_locations)));
+        Json::Object(fields)
+    }
+
+    /// Add a group of diagnostic messages to the log.
+    pub fn emit(&mut self, msgs: &[Diagnostic]) {
+        for msg in msgs {
+            if let Some(ref code) = msg.code {
+                if !self.rules.contains(code) {
+                    self.rules.push(code.clone());
+                }
+            }
+            let result = self.diagnostic_to_json(msg);
+            self.results.push(result);
+        }
+    }
+
+    /// Write the SARIF log containing all diagnostics emitted so far.
+    ///
+    /// The buffered diagnostics are cleared, so a subsequent call starts a new log.
+    ///
+    /// # Panics
+    ///
+    /// If writing to the output fails. Use `try_finish` to handle the error instead.
+    pub fn finish(&mut self) {
+        if let Err(e) = self.try_finish() {
+            panic!("failed to emit error: {}", e);
+        }
+    }
+
+    /// Write the SARIF log, returning any error from writing to the output.
+    ///
+    /// The buffered diagnostics are only cleared if the log is written, so a failed call can be
+    /// retried.
+    pub fn try_finish(&mut self) -> io::Result<()> {
+        let rules = self.rules.iter().map(|id| Json::Object(vec![("id", Json::String(id.clone()))]));
+
+        let driver = Json::Object(vec![
+            ("name", (&self.tool_name[..]).into()),
+            ("rules", Json::Array(rules.collect())),
+        ]);
+
+        let run = Json::Object(vec![
+            ("tool", Json::Object(vec![("driver", driver)])),
+            ("columnKind", "unicodeCodePoints".into()),
+            ("results", Json::Array(self.results.clone())),
+        ]);
+
+        let log = Json::Object(vec![
+            ("$schema", SARIF_SCHEMA.into()),
+            ("version", "2.1.0".into()),
+            ("runs", Json::Array(vec![run])),
+        ]);
+
+        writeln!(self.dst, "{}", log)?;
+        self.dst.flush()?;
+        self.rules.clear();
+        self.results.clear();
+        Ok(())
+    }
+}
+
+/// Convert a file name to a URI reference, with `\` separators replaced by `/` and all
+/// characters other than `/` and unreserved ones percent-encoded.
+fn file_uri(name: &str) -> String {
+    let mut uri = String::new();
+    for &b in name.as_bytes() {
+        match b {
+            b'\\' => uri.push('/'),
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                uri.push(b as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    uri
+}
+
+impl<'a> Emit for SarifEmitter<'a> {
+    fn emit(&mut self, msgs: &[Diagnostic]) -> io::Result<()> {
+        SarifEmitter::emit(self, msgs);
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.try_finish()
+    }
+}
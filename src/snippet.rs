@@ -10,6 +10,7 @@
 
 // Code for annotating snippets.
 use Level;
+use highlight::SyntaxClass;
 
 #[derive(Clone, Debug, PartialOrd, Ord, PartialEq, Eq)]
 pub struct Line {
@@ -119,30 +120,20 @@ pub struct Annotation {
 impl Annotation {
     /// Whether this annotation is a vertical line placeholder.
     pub fn is_line(&self) -> bool {
-        if let AnnotationType::MultilineLine(_) = self.annotation_type {
-            true
-        } else {
-            false
-        }
+        matches!(self.annotation_type, AnnotationType::MultilineLine(_))
     }
 
     pub fn is_multiline(&self) -> bool {
-        match self.annotation_type {
-            AnnotationType::Multiline(_) |
-            AnnotationType::MultilineStart(_) |
-            AnnotationType::MultilineLine(_) |
-            AnnotationType::MultilineEnd(_) => true,
-            _ => false,
-        }
+        matches!(self.annotation_type,
+                 AnnotationType::Multiline(_) |
+                 AnnotationType::MultilineStart(_) |
+                 AnnotationType::MultilineLine(_) |
+                 AnnotationType::MultilineEnd(_))
     }
 
     pub fn len(&self) -> usize {
         // Account for usize underflows
-        if self.end_col > self.start_col {
-            self.end_col - self.start_col
-        } else {
-            self.start_col - self.end_col
-        }
+        self.end_col.abs_diff(self.start_col)
     }
 
     pub fn has_label(&self) -> bool {
@@ -157,7 +148,7 @@ impl Annotation {
             //       |
             //
             // Note that this would be the complete output users would see.
-            label.len() > 0
+            !label.is_empty()
         } else {
             false
         }
@@ -165,32 +156,56 @@ impl Annotation {
 
     pub fn takes_space(&self) -> bool {
         // Multiline annotations always have to keep vertical space.
-        match self.annotation_type {
-            AnnotationType::MultilineStart(_) |
-            AnnotationType::MultilineEnd(_) => true,
-            _ => false,
-        }
+        matches!(self.annotation_type,
+                 AnnotationType::MultilineStart(_) |
+                 AnnotationType::MultilineEnd(_))
     }
 }
 
-#[derive(Debug)]
+/// A run of text sharing a single style.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct StyledString {
     pub text: String,
     pub style: Style,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq)]
-#[allow(dead_code)]
+/// The role of a piece of rendered text, used to choose its formatting.
+///
+/// More styles may be added in future versions, so a `match` on a `Style` needs a wildcard arm.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::enum_variant_names)]
+#[non_exhaustive]
 pub enum Style {
+    /// The message of a diagnostic.
     HeaderMsg,
+    /// The file name, line and column of a location.
     LineAndColumn,
+    /// Line numbers, and the separators and arrows of the left margin.
     LineNumber,
+    /// Quoted source code.
     Quotation,
+    /// Underline of a primary span.
     UnderlinePrimary,
+    /// Underline of a secondary span.
     UnderlineSecondary,
+    /// Label of a primary span.
     LabelPrimary,
+    /// Label of a secondary span.
     LabelSecondary,
+    /// Unstyled text.
     NoStyle,
+    /// The level name and code of a diagnostic.
     Level(Level),
+    /// Emphasized text.
     Highlight,
+    /// Markers under code inserted or replaced by a suggestion.
+    Addition,
+    /// Markers under code deleted by a suggestion.
+    Removal,
+    /// Quoted source code colored by a `Highlighter`.
+    Syntax(SyntaxClass),
+    /// Headings in error code explanations.
+    Heading,
+    /// Code in error code explanations.
+    Code,
 }
@@ -11,10 +11,106 @@
 // Code for creating styled buffers
 
 use snippet::{Style, StyledString};
+use std::cmp::{max, min};
+use unicode_width::UnicodeWidthChar;
 
+/// The number of terminal columns used to display a character.
+///
+/// Control characters, which have no defined width, are counted as one column.
+pub fn char_width(c: char) -> usize {
+    UnicodeWidthChar::width(c).unwrap_or(1)
+}
+
+/// The number of terminal columns used to display a string.
+pub fn str_width(s: &str) -> usize {
+    s.chars().map(char_width).sum()
+}
+
+/// The number of terminal columns used to display the first `char_col` characters of a string,
+/// with tabs expanded to the next multiple of `tab_width`.
+pub fn display_col(s: &str, char_col: usize, tab_width: usize) -> usize {
+    s.chars().take(char_col).fold(0, |col, c| {
+        if c == '\t' {
+            next_tab_stop(col, tab_width)
+        } else {
+            col + char_width(c)
+        }
+    })
+}
+
+/// Replace the tabs in a string with spaces, up to the next multiple of `tab_width`.
+pub fn expand_tabs(s: &str, tab_width: usize) -> String {
+    let mut expanded = String::with_capacity(s.len());
+    let mut col = 0;
+    for c in s.chars() {
+        if c == '\t' {
+            let next = next_tab_stop(col, tab_width);
+            expanded.extend((col..next).map(|_| ' '));
+            col = next;
+        } else {
+            expanded.push(c);
+            col += char_width(c);
+        }
+    }
+    expanded
+}
+
+/// The characters of a string displayed within the columns `left..right`.
+///
+/// Wide characters that only partly fit are replaced with spaces.
+pub fn slice_columns(s: &str, left: usize, right: usize) -> String {
+    let mut sliced = String::new();
+    let mut col = 0;
+    for c in s.chars() {
+        let width = char_width(c);
+        if col >= left && col + width <= right {
+            sliced.push(c);
+        } else if col < right && col + width > left {
+            sliced.extend((max(col, left)..min(col + width, right)).map(|_| ' '));
+        }
+        col += width;
+        if col >= right {
+            break;
+        }
+    }
+    sliced
+}
+
+/// Split text into parts with `style`, and parts between backticks with `code_style`.
+///
+/// The backticks are included in the emphasized parts. A backtick without a matching closing
+/// backtick is left as it is.
+pub fn split_backticks(text: &str, style: Style, code_style: Style) -> Vec<(String, Style)> {
+    let pieces: Vec<&str> = text.split('`').collect();
+    let mut parts = vec![];
+    for (i, piece) in pieces.iter().enumerate() {
+        if i % 2 == 0 {
+            parts.push((piece.to_string(), style));
+        } else if i + 1 < pieces.len() {
+            parts.push((format!("`{}`", piece), code_style));
+        } else {
+            parts.push((format!("`{}", piece), style));
+        }
+    }
+    parts.retain(|(text, _)| !text.is_empty());
+    parts
+}
+
+fn next_tab_stop(col: usize, tab_width: usize) -> usize {
+    match col.checked_div(tab_width) {
+        Some(stops) => (stops + 1) * tab_width,
+        None => col,
+    }
+}
+
+/// A grid of styled text, indexed by line and terminal column.
+///
+/// Each cell holds the text displayed in one column: a character followed by any zero-width
+/// characters combining with it, or an empty string for the columns covered by the preceding
+/// wide character.
 #[derive(Debug)]
 pub struct StyledBuffer {
-    text: Vec<Vec<char>>,
+    text: Vec<Vec<String>>,
     styles: Vec<Vec<Style>>,
 }
 
@@ -26,31 +122,19 @@ impl StyledBuffer {
         }
     }
 
-    pub fn copy_tabs(&mut self, row: usize) {
-        if row < self.text.len() {
-            for i in row + 1..self.text.len() {
-                for j in 0..self.text[i].len() {
-                    if self.text[row].len() > j && self.text[row][j] == '\t' &&
-                       self.text[i][j] == ' ' {
-                        self.text[i][j] = '\t';
-                    }
-                }
-            }
-        }
-    }
-
-    pub fn render(&mut self) -> Vec<Vec<StyledString>> {
+    pub fn render(&self) -> Vec<Vec<StyledString>> {
         let mut output: Vec<Vec<StyledString>> = vec![];
         let mut styled_vec: Vec<StyledString> = vec![];
 
-        // before we render, do a little patch-up work to support tabs
-        self.copy_tabs(3);
-
         for (row, row_style) in self.text.iter().zip(&self.styles) {
             let mut current_style = Style::NoStyle;
             let mut current_text = String::new();
 
-            for (&c, &s) in row.iter().zip(row_style) {
+            for (c, &s) in row.iter().zip(row_style) {
+                if c.is_empty() {
+                    // Covered by the previous wide character
+                    continue;
+                }
                 if s != current_style {
                     if !current_text.is_empty() {
                         styled_vec.push(StyledString {
@@ -61,7 +145,7 @@ impl StyledBuffer {
                     current_style = s;
                     current_text = String::new();
                 }
-                current_text.push(c);
+                current_text.push_str(c);
             }
             if !current_text.is_empty() {
                 styled_vec.push(StyledString {
@@ -86,39 +170,58 @@ impl StyledBuffer {
         }
     }
 
-    pub fn putc(&mut self, line: usize, col: usize, chr: char, style: Style) {
+    fn set_cell(&mut self, line: usize, col: usize, text: String, style: Style) {
         self.ensure_lines(line);
-        if col < self.text[line].len() {
-            self.text[line][col] = chr;
-            self.styles[line][col] = style;
-        } else {
-            let mut i = self.text[line].len();
-            while i < col {
-                self.text[line].push(' ');
-                self.styles[line].push(Style::NoStyle);
-                i += 1;
-            }
-            self.text[line].push(chr);
-            self.styles[line].push(style);
+        while self.text[line].len() <= col {
+            self.text[line].push(" ".to_owned());
+            self.styles[line].push(Style::NoStyle);
+        }
+
+        // Don't leave half of a wide character behind
+        if self.text[line][col].is_empty() && col > 0 {
+            self.text[line][col - 1] = " ".to_owned();
+        }
+        let old_width = str_width(&self.text[line][col]);
+        for i in col + 1..min(col + old_width, self.text[line].len()) {
+            self.text[line][i] = " ".to_owned();
+        }
+
+        self.text[line][col] = text;
+        self.styles[line][col] = style;
+    }
+
+    pub fn putc(&mut self, line: usize, col: usize, chr: char, style: Style) {
+        self.set_cell(line, col, chr.to_string(), style);
+        for i in 1..char_width(chr) {
+            self.set_cell(line, col + i, String::new(), style);
         }
     }
 
     pub fn puts(&mut self, line: usize, col: usize, string: &str, style: Style) {
         let mut n = col;
         for c in string.chars() {
+            if char_width(c) == 0 && n > col {
+                // Combine with the last character written
+                let mut prev = n - 1;
+                while prev > col && self.text[line][prev].is_empty() {
+                    prev -= 1;
+                }
+                self.text[line][prev].push(c);
+                continue;
+            }
             self.putc(line, n, c, style);
-            n += 1;
+            n += char_width(c).max(1);
         }
     }
 
     pub fn prepend(&mut self, line: usize, string: &str, style: Style) {
         self.ensure_lines(line);
-        let string_len = string.len();
+        let string_len = str_width(string);
 
         // Push the old content over to make room for new content
         for _ in 0..string_len {
             self.styles[line].insert(0, Style::NoStyle);
-            self.text[line].insert(0, ' ');
+            self.text[line].insert(0, " ".to_owned());
         }
 
         self.puts(line, 0, string, style);
@@ -133,6 +236,29 @@ impl StyledBuffer {
         }
     }
 
+    /// Set `style` for `line`, `col_start..col_end`, where the cells already exist and either
+    /// `overwrite` is true or their style is `Style::NoStyle` or `Style::Quotation`.
+    pub fn set_style_range(&mut self,
+                           line: usize,
+                           col_start: usize,
+                           col_end: usize,
+                           style: Style,
+                           overwrite: bool) {
+        for col in col_start..col_end {
+            self.set_style(line, col, style, overwrite);
+        }
+    }
+
+    /// Set `style` for `line`, `col`, where the cell already exists and either `overwrite` is
+    /// true or its style is `Style::NoStyle` or `Style::Quotation`.
+    pub fn set_style(&mut self, line: usize, col: usize, style: Style, overwrite: bool) {
+        if let Some(s) = self.styles.get_mut(line).and_then(|line| line.get_mut(col)) {
+            if overwrite || *s == Style::NoStyle || *s == Style::Quotation {
+                *s = style;
+            }
+        }
+    }
+
     pub fn num_lines(&self) -> usize {
         self.text.len()
     }
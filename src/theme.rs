@@ -0,0 +1,259 @@
+use termcolor::{Color, ColorSpec};
+use { Level, Style, SyntaxClass };
+
+/// Colors and text attributes used for each part of the output.
+///
+/// The specs for `Style::UnderlinePrimary`, `Style::LabelPrimary` and `Style::Level` are
+/// combined with the spec of the diagnostic's level, which gives their color unless they set one.
+///
+/// Fields may be added in future versions along with new styles, so a custom theme is created
+/// by modifying one of the presets rather than with a struct literal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Theme {
+    /// Spec for `Style::HeaderMsg`.
+    pub header_msg: ColorSpec,
+    /// Spec for `Style::LineAndColumn`.
+    pub line_and_column: ColorSpec,
+    /// Spec for `Style::LineNumber`.
+    pub line_number: ColorSpec,
+    /// Spec for `Style::Quotation`.
+    pub quotation: ColorSpec,
+    /// Spec for `Style::UnderlinePrimary`, combined with the spec of the level.
+    pub underline_primary: ColorSpec,
+    /// Spec for `Style::UnderlineSecondary`.
+    pub underline_secondary: ColorSpec,
+    /// Spec for `Style::LabelPrimary`, combined with the spec of the level.
+    pub label_primary: ColorSpec,
+    /// Spec for `Style::LabelSecondary`.
+    pub label_secondary: ColorSpec,
+    /// Spec for `Style::NoStyle`.
+    pub no_style: ColorSpec,
+    /// Spec for `Style::Level`, combined with the spec of the level.
+    pub level: ColorSpec,
+    /// Spec for `Style::Highlight`.
+    pub highlight: ColorSpec,
+    /// Spec for `Style::Addition`.
+    pub addition: ColorSpec,
+    /// Spec for `Style::Removal`.
+    pub removal: ColorSpec,
+
+    /// Spec for `Style::Heading`.
+    pub heading: ColorSpec,
+    /// Spec for `Style::Code`.
+    pub code: ColorSpec,
+
+    /// Spec for `SyntaxClass::Keyword`.
+    pub keyword: ColorSpec,
+    /// Spec for `SyntaxClass::Type`.
+    pub type_name: ColorSpec,
+    /// Spec for `SyntaxClass::Function`.
+    pub function: ColorSpec,
+    /// Spec for `SyntaxClass::String`.
+    pub string: ColorSpec,
+    /// Spec for `SyntaxClass::Number`.
+    pub number: ColorSpec,
+    /// Spec for `SyntaxClass::Comment`.
+    pub comment: ColorSpec,
+    /// Spec for `SyntaxClass::Operator`.
+    pub operator: ColorSpec,
+
+    /// Spec for `Level::Bug`.
+    pub bug: ColorSpec,
+    /// Spec for `Level::Error`.
+    pub error: ColorSpec,
+    /// Spec for `Level::Warning`.
+    pub warning: ColorSpec,
+    /// Spec for `Level::Note`.
+    pub note: ColorSpec,
+    /// Spec for `Level::Help`.
+    pub help: ColorSpec,
+}
+
+fn spec(fg: Option<Color>, bold: bool, intense: bool) -> ColorSpec {
+    let mut spec = ColorSpec::new();
+    spec.set_fg(fg).set_bold(bold).set_intense(intense);
+    spec
+}
+
+impl Theme {
+    /// The default colors, matching rustc, for terminals with a dark background.
+    pub fn dark() -> Theme {
+        let blue = if cfg!(windows) { Color::Cyan } else { Color::Blue };
+        let header_msg = if cfg!(windows) {
+            spec(Some(Color::White), true, true)
+        } else {
+            spec(None, true, false)
+        };
+        Theme {
+            header_msg,
+            line_and_column: ColorSpec::new(),
+            line_number: spec(Some(blue), true, true),
+            quotation: ColorSpec::new(),
+            underline_primary: spec(None, true, false),
+            underline_secondary: spec(Some(blue), true, true),
+            label_primary: spec(None, true, false),
+            label_secondary: spec(Some(blue), true, true),
+            no_style: ColorSpec::new(),
+            level: spec(None, true, false),
+            highlight: spec(None, true, false),
+            addition: spec(Some(Color::Green), false, true),
+            removal: spec(Some(Color::Red), false, true),
+            heading: spec(None, true, false),
+            code: spec(Some(Color::Cyan), false, false),
+            keyword: spec(Some(Color::Magenta), false, true),
+            type_name: spec(Some(Color::Yellow), false, true),
+            function: spec(Some(blue), false, true),
+            string: spec(Some(Color::Green), false, false),
+            number: spec(Some(Color::Cyan), false, false),
+            comment: spec(Some(Color::White), false, false),
+            operator: ColorSpec::new(),
+            bug: spec(Some(Color::Red), false, true),
+            error: spec(Some(Color::Red), false, true),
+            warning: spec(Some(Color::Yellow), false, cfg!(windows)),
+            note: spec(Some(Color::Green), false, true),
+            help: spec(Some(Color::Cyan), false, true),
+        }
+    }
+
+    /// Colors for terminals with a light background, avoiding yellow, cyan and intense colors.
+    pub fn light() -> Theme {
+        Theme {
+            header_msg: spec(None, true, false),
+            line_and_column: ColorSpec::new(),
+            line_number: spec(Some(Color::Blue), true, false),
+            quotation: ColorSpec::new(),
+            underline_primary: spec(None, true, false),
+            underline_secondary: spec(Some(Color::Blue), true, false),
+            label_primary: spec(None, true, false),
+            label_secondary: spec(Some(Color::Blue), true, false),
+            no_style: ColorSpec::new(),
+            level: spec(None, true, false),
+            highlight: spec(None, true, false),
+            addition: spec(Some(Color::Green), false, false),
+            removal: spec(Some(Color::Red), false, false),
+            heading: spec(None, true, false),
+            code: spec(Some(Color::Blue), false, false),
+            keyword: spec(Some(Color::Magenta), false, false),
+            type_name: spec(Some(Color::Blue), false, false),
+            function: spec(Some(Color::Blue), false, false),
+            string: spec(Some(Color::Green), false, false),
+            number: spec(Some(Color::Red), false, false),
+            comment: spec(Some(Color::Black), false, true),
+            operator: ColorSpec::new(),
+            bug: spec(Some(Color::Red), false, false),
+            error: spec(Some(Color::Red), false, false),
+            warning: spec(Some(Color::Magenta), false, false),
+            note: spec(Some(Color::Green), false, false),
+            help: spec(Some(Color::Blue), false, false),
+        }
+    }
+
+    /// No colors, using bold text to emphasize the parts that are normally colored.
+    pub fn monochrome() -> Theme {
+        let bold = spec(None, true, false);
+        Theme {
+            header_msg: bold.clone(),
+            line_and_column: ColorSpec::new(),
+            line_number: bold.clone(),
+            quotation: ColorSpec::new(),
+            underline_primary: bold.clone(),
+            underline_secondary: ColorSpec::new(),
+            label_primary: bold.clone(),
+            label_secondary: ColorSpec::new(),
+            no_style: ColorSpec::new(),
+            level: bold.clone(),
+            highlight: bold.clone(),
+            addition: bold.clone(),
+            removal: bold.clone(),
+            heading: bold.clone(),
+            code: ColorSpec::new(),
+            keyword: bold.clone(),
+            type_name: ColorSpec::new(),
+            function: ColorSpec::new(),
+            string: ColorSpec::new(),
+            number: ColorSpec::new(),
+            comment: ColorSpec::new(),
+            operator: ColorSpec::new(),
+            bug: bold.clone(),
+            error: bold.clone(),
+            warning: bold.clone(),
+            note: bold.clone(),
+            help: bold,
+        }
+    }
+
+    /// Get the spec for a level.
+    pub fn level_spec(&self, level: Level) -> &ColorSpec {
+        match level {
+            Level::Bug => &self.bug,
+            Level::Error => &self.error,
+            Level::Warning => &self.warning,
+            Level::Note => &self.note,
+            Level::Help => &self.help,
+        }
+    }
+
+    /// Get the spec for a syntax class.
+    pub fn syntax_spec(&self, class: SyntaxClass) -> &ColorSpec {
+        match class {
+            SyntaxClass::Keyword => &self.keyword,
+            SyntaxClass::Type => &self.type_name,
+            SyntaxClass::Function => &self.function,
+            SyntaxClass::String => &self.string,
+            SyntaxClass::Number => &self.number,
+            SyntaxClass::Comment => &self.comment,
+            SyntaxClass::Operator => &self.operator,
+        }
+    }
+
+    /// Combine the spec of a level with `spec`, which takes precedence where it sets a color or
+    /// an attribute.
+    fn with_level(&self, level: Level, spec: &ColorSpec) -> ColorSpec {
+        let mut combined = self.level_spec(level).clone();
+        if let Some(&fg) = spec.fg() {
+            combined.set_fg(Some(fg));
+        }
+        if let Some(&bg) = spec.bg() {
+            combined.set_bg(Some(bg));
+        }
+        if spec.bold() {
+            combined.set_bold(true);
+        }
+        if spec.intense() {
+            combined.set_intense(true);
+        }
+        if spec.underline() {
+            combined.set_underline(true);
+        }
+        combined
+    }
+
+    /// Get the spec for a style, within a message of the given level.
+    pub fn style_spec(&self, level: Level, style: Style) -> ColorSpec {
+        match style {
+            Style::HeaderMsg => self.header_msg.clone(),
+            Style::LineAndColumn => self.line_and_column.clone(),
+            Style::LineNumber => self.line_number.clone(),
+            Style::Quotation => self.quotation.clone(),
+            Style::UnderlinePrimary => self.with_level(level, &self.underline_primary),
+            Style::UnderlineSecondary => self.underline_secondary.clone(),
+            Style::LabelPrimary => self.with_level(level, &self.label_primary),
+            Style::LabelSecondary => self.label_secondary.clone(),
+            Style::NoStyle => self.no_style.clone(),
+            Style::Level(level) => self.with_level(level, &self.level),
+            Style::Highlight => self.highlight.clone(),
+            Style::Addition => self.addition.clone(),
+            Style::Removal => self.removal.clone(),
+            Style::Syntax(class) => self.syntax_spec(class).clone(),
+            Style::Heading => self.heading.clone(),
+            Style::Code => self.code.clone(),
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme::dark()
+    }
+}
@@ -0,0 +1,107 @@
+extern crate codemap;
+extern crate codemap_diagnostic;
+
+mod common;
+
+use codemap::CodeMap;
+use codemap_diagnostic::{ Charset, Diagnostic, Level };
+use common::{ child, diagnostic, emit, primary, secondary, suggestion };
+
+const MAIN: &str = "\
+fn main() {
+    let x = f(1,
+              2);
+    g(x);
+}
+";
+
+const LIB: &str = "pub fn g(x: u32) {}\n";
+
+fn render(charset: Charset) -> String {
+    let mut code_map = CodeMap::new();
+    let main = code_map.add_file("main.rs".to_owned(), MAIN.to_owned()).span;
+    let lib = code_map.add_file("lib.rs".to_owned(), LIB.to_owned()).span;
+
+    let at = |needle: &str| MAIN.find(needle).unwrap() as u64;
+    let d = Diagnostic {
+        spans: vec![
+            primary(main.subspan(at("f(1"), at(");") + 1), Some("returns `i32`")),
+            secondary(main.subspan(at("let x"), at("let x") + 5), None),
+            secondary(main.subspan(0, MAIN.len() as u64 - 1), Some("in this function")),
+            secondary(lib.subspan(9, 15), Some("expected due to this")),
+        ],
+        children: vec![
+            child(Level::Note, "called here", vec![primary(main.subspan(at("g(x)"), at("g(x)") + 4), None)]),
+        ],
+        suggestions: vec![suggestion(main.subspan(at(");") + 1, at(");") + 1), ".into()")],
+        ..diagnostic(Level::Error, "mismatched types")
+    };
+    emit(Some(&code_map), &[d], |e| e.charset(charset))
+}
+
+/// The same output as before `Charset` was added.
+#[test]
+fn ascii() {
+    assert_eq!(render(Charset::Ascii), "\
+error: mismatched types
+ --> main.rs:2:13
+  |
+1 |  / fn main() {
+2 |  |     let x = f(1,
+  |  |_____-----___^
+3 | ||               2);
+  | ||________________^ returns `i32`
+4 |  |     g(x);
+5 |  | }
+  |  |_- in this function
+  | 
+ ::: lib.rs
+  |
+1 |    pub fn g(x: u32) {}
+  |             ------ expected due to this
+  |
+note: called here
+ --> main.rs:4:5
+  |
+4 |     g(x);
+  |     ^^^^
+help: try
+  |
+3 |               2).into();
+  |                 +++++++
+
+");
+}
+
+#[test]
+fn unicode() {
+    assert_eq!(render(Charset::Unicode), "\
+error: mismatched types
+ ╭▸ main.rs:2:13
+  │
+1 │  ╭ fn main() {
+2 │  │     let x = f(1,
+  │ ╭│─────────────━
+3 │ ││               2);
+  │ ╰│────────────────━ returns `i32`
+4 │  │     g(x);
+5 │  │ }
+  │  ╰── in this function
+  │ 
+ ⸬ lib.rs
+  │
+1 │    pub fn g(x: u32) {}
+  │             ────── expected due to this
+  │
+note: called here
+ ╭▸ main.rs:4:5
+  │
+4 │     g(x);
+  │     ━━━━
+help: try
+  │
+3 │               2).into();
+  │                 +++++++
+
+");
+}
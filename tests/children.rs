@@ -0,0 +1,36 @@
+extern crate codemap;
+extern crate codemap_diagnostic;
+
+mod common;
+
+use codemap_diagnostic::{ Diagnostic, Level };
+use common::{ child, code_map, diagnostic, emit, primary };
+
+#[test]
+fn children_share_line_number_width() {
+    let source = "let x = 1;\n\n\n\n\n\n\n\n\n\n\nlet y = x;\n";
+    let (code_map, file_span) = code_map(source);
+    let d = Diagnostic {
+        spans: vec![primary(file_span.subspan(4, 5), Some("first defined here"))],
+        children: vec![
+            child(Level::Note, "previous use", vec![primary(file_span.subspan(29, 30), None)]),
+            child(Level::Help, "consider renaming", vec![]),
+        ],
+        ..diagnostic(Level::Error, "redefined")
+    };
+    assert_eq!(emit(Some(&code_map), &[d], |e| e), "\
+error: redefined
+  --> test.rs:1:5
+   |
+1  | let x = 1;
+   |     ^ first defined here
+   |
+note: previous use
+  --> test.rs:12:9
+   |
+12 | let y = x;
+   |         ^
+   = help: consider renaming
+
+");
+}
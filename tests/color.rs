@@ -0,0 +1,187 @@
+extern crate codemap;
+extern crate codemap_diagnostic;
+extern crate termcolor;
+
+mod common;
+
+use codemap::{ CodeMap, Span };
+use codemap_diagnostic::{ Diagnostic, Emitter, Level, Style, Theme };
+use common::{ code_map, diagnostic, primary, secondary };
+use termcolor::{ Ansi, Color, ColorSpec };
+
+/// Emits through `Emitter::colored` into ANSI escape sequences, written as `\e[...m`.
+fn emit_colored<F>(code_map: &CodeMap, diagnostics: &[Diagnostic], configure: F) -> String
+    where F: for<'a> FnOnce(Emitter<'a>) -> Emitter<'a>
+{
+    let mut out = Vec::new();
+    configure(Emitter::colored(Box::new(Ansi::new(&mut out)), Some(code_map))).emit(diagnostics);
+    String::from_utf8(out).unwrap().replace('\u{1b}', "\\e")
+}
+
+// The default colors are different on Windows.
+#[test]
+#[cfg(not(windows))]
+fn colored() {
+    let (code_map, span) = code_map("let x = y;\n");
+    let d = Diagnostic {
+        code: Some("E0425".to_owned()),
+        spans: vec![
+            primary(span.subspan(8, 9), Some("not found")),
+            secondary(span.subspan(4, 5), Some("binding")),
+        ],
+        ..diagnostic(Level::Error, "cannot find value `y`")
+    };
+    // The level and primary underline and label are bright red, the line numbers and secondary
+    // span bright blue, all bold.
+    assert_eq!(emit_colored(&code_map, &[d], |e| e), r"\e[0m\e[1m\e[38;5;9merror[E0425]\e[0m\e[0m\e[1m: cannot find value `y`\e[0m
+\e[0m \e[0m\e[0m\e[1m\e[38;5;12m--> \e[0m\e[0mtest.rs:1:9\e[0m
+\e[0m  \e[0m\e[0m\e[1m\e[38;5;12m|\e[0m
+\e[0m\e[1m\e[38;5;12m1\e[0m\e[0m \e[0m\e[0m\e[1m\e[38;5;12m| \e[0m\e[0mlet x = y;\e[0m
+\e[0m  \e[0m\e[0m\e[1m\e[38;5;12m| \e[0m\e[0m    \e[0m\e[0m\e[1m\e[38;5;12m-\e[0m\e[0m   \e[0m\e[0m\e[1m\e[38;5;9m^\e[0m\e[0m \e[0m\e[0m\e[1m\e[38;5;9mnot found\e[0m
+\e[0m  \e[0m\e[0m\e[1m\e[38;5;12m| \e[0m\e[0m    \e[0m\e[0m\e[1m\e[38;5;12m|\e[0m
+\e[0m  \e[0m\e[0m\e[1m\e[38;5;12m| \e[0m\e[0m    \e[0m\e[0m\e[1m\e[38;5;12mbinding\e[0m
+
+");
+}
+
+/// The specs of the colors that were hardcoded before themes were added.
+fn previous_spec(level: Level, style: Style) -> ColorSpec {
+    let level_color = |level| {
+        let mut spec = ColorSpec::new();
+        match level {
+            Level::Bug | Level::Error => spec.set_fg(Some(Color::Red)).set_intense(true),
+            Level::Warning => spec.set_fg(Some(Color::Yellow)).set_intense(cfg!(windows)),
+            Level::Note => spec.set_fg(Some(Color::Green)).set_intense(true),
+            Level::Help => spec.set_fg(Some(Color::Cyan)).set_intense(true),
+        };
+        spec
+    };
+    let blue = if cfg!(windows) { Color::Cyan } else { Color::Blue };
+
+    let mut spec = ColorSpec::new();
+    match style {
+        Style::LineNumber | Style::UnderlineSecondary | Style::LabelSecondary => {
+            spec.set_bold(true).set_intense(true).set_fg(Some(blue));
+        }
+        Style::HeaderMsg => {
+            spec.set_bold(true);
+            if cfg!(windows) {
+                spec.set_intense(true).set_fg(Some(Color::White));
+            }
+        }
+        Style::UnderlinePrimary | Style::LabelPrimary => {
+            spec = level_color(level);
+            spec.set_bold(true);
+        }
+        Style::Level(level) => {
+            spec = level_color(level);
+            spec.set_bold(true);
+        }
+        Style::Highlight => {
+            spec.set_bold(true);
+        }
+        _ => {}
+    }
+    spec
+}
+
+#[test]
+fn dark_theme_matches_previous_colors() {
+    let levels = [Level::Bug, Level::Error, Level::Warning, Level::Note, Level::Help];
+    let mut styles = vec![
+        Style::HeaderMsg,
+        Style::LineAndColumn,
+        Style::LineNumber,
+        Style::Quotation,
+        Style::UnderlinePrimary,
+        Style::UnderlineSecondary,
+        Style::LabelPrimary,
+        Style::LabelSecondary,
+        Style::NoStyle,
+        Style::Highlight,
+    ];
+    styles.extend(levels.iter().map(|&level| Style::Level(level)));
+
+    let theme = Theme::dark();
+    for &level in &levels {
+        for &style in &styles {
+            assert_eq!(theme.style_spec(level, style), previous_spec(level, style),
+                       "{:?} in {:?}", style, level);
+        }
+    }
+    assert_eq!(Theme::default(), theme);
+}
+
+fn warning(span: Span) -> Diagnostic {
+    Diagnostic {
+        spans: vec![
+            primary(span.subspan(4, 5), Some("unused")),
+            secondary(span.subspan(8, 9), None),
+        ],
+        ..diagnostic(Level::Warning, "unused variable `x`")
+    }
+}
+
+#[test]
+#[cfg(not(windows))]
+fn dark_theme() {
+    let (code_map, span) = code_map("let x = 1;\n");
+    let out = emit_colored(&code_map, &[warning(span)], |e| e.theme(Theme::dark()));
+    assert_eq!(out, r"\e[0m\e[1m\e[33mwarning\e[0m\e[0m\e[1m: unused variable `x`\e[0m
+\e[0m \e[0m\e[0m\e[1m\e[38;5;12m--> \e[0m\e[0mtest.rs:1:5\e[0m
+\e[0m  \e[0m\e[0m\e[1m\e[38;5;12m|\e[0m
+\e[0m\e[1m\e[38;5;12m1\e[0m\e[0m \e[0m\e[0m\e[1m\e[38;5;12m| \e[0m\e[0mlet x = 1;\e[0m
+\e[0m  \e[0m\e[0m\e[1m\e[38;5;12m| \e[0m\e[0m    \e[0m\e[0m\e[1m\e[33m^\e[0m\e[0m   \e[0m\e[0m\e[1m\e[38;5;12m-\e[0m
+\e[0m  \e[0m\e[0m\e[1m\e[38;5;12m| \e[0m\e[0m    \e[0m\e[0m\e[1m\e[33m|\e[0m
+\e[0m  \e[0m\e[0m\e[1m\e[38;5;12m| \e[0m\e[0m    \e[0m\e[0m\e[1m\e[33munused\e[0m
+
+");
+}
+
+#[test]
+fn light_theme() {
+    let (code_map, span) = code_map("let x = 1;\n");
+    let out = emit_colored(&code_map, &[warning(span)], |e| e.theme(Theme::light()));
+    assert_eq!(out, r"\e[0m\e[1m\e[35mwarning\e[0m\e[0m\e[1m: unused variable `x`\e[0m
+\e[0m \e[0m\e[0m\e[1m\e[34m--> \e[0m\e[0mtest.rs:1:5\e[0m
+\e[0m  \e[0m\e[0m\e[1m\e[34m|\e[0m
+\e[0m\e[1m\e[34m1\e[0m\e[0m \e[0m\e[0m\e[1m\e[34m| \e[0m\e[0mlet x = 1;\e[0m
+\e[0m  \e[0m\e[0m\e[1m\e[34m| \e[0m\e[0m    \e[0m\e[0m\e[1m\e[35m^\e[0m\e[0m   \e[0m\e[0m\e[1m\e[34m-\e[0m
+\e[0m  \e[0m\e[0m\e[1m\e[34m| \e[0m\e[0m    \e[0m\e[0m\e[1m\e[35m|\e[0m
+\e[0m  \e[0m\e[0m\e[1m\e[34m| \e[0m\e[0m    \e[0m\e[0m\e[1m\e[35munused\e[0m
+
+");
+}
+
+#[test]
+fn monochrome_theme() {
+    let (code_map, span) = code_map("let x = 1;\n");
+    let out = emit_colored(&code_map, &[warning(span)], |e| e.theme(Theme::monochrome()));
+    assert_eq!(out, r"\e[0m\e[1mwarning\e[0m\e[0m\e[1m: unused variable `x`\e[0m
+\e[0m \e[0m\e[0m\e[1m--> \e[0m\e[0mtest.rs:1:5\e[0m
+\e[0m  \e[0m\e[0m\e[1m|\e[0m
+\e[0m\e[1m1\e[0m\e[0m \e[0m\e[0m\e[1m| \e[0m\e[0mlet x = 1;\e[0m
+\e[0m  \e[0m\e[0m\e[1m| \e[0m\e[0m    \e[0m\e[0m\e[1m^\e[0m\e[0m   \e[0m\e[0m-\e[0m
+\e[0m  \e[0m\e[0m\e[1m| \e[0m\e[0m    \e[0m\e[0m\e[1m|\e[0m
+\e[0m  \e[0m\e[0m\e[1m| \e[0m\e[0m    \e[0m\e[0m\e[1munused\e[0m
+
+");
+}
+
+#[test]
+fn custom_theme() {
+    let (code_map, span) = code_map("let x = 1;\n");
+    let mut theme = Theme::monochrome();
+    theme.underline_primary.set_fg(Some(Color::Magenta));
+    theme.label_primary.set_underline(true);
+    let out = emit_colored(&code_map, &[warning(span)], |e| e.theme(theme));
+    assert_eq!(out, r"\e[0m\e[1mwarning\e[0m\e[0m\e[1m: unused variable `x`\e[0m
+\e[0m \e[0m\e[0m\e[1m--> \e[0m\e[0mtest.rs:1:5\e[0m
+\e[0m  \e[0m\e[0m\e[1m|\e[0m
+\e[0m\e[1m1\e[0m\e[0m \e[0m\e[0m\e[1m| \e[0m\e[0mlet x = 1;\e[0m
+\e[0m  \e[0m\e[0m\e[1m| \e[0m\e[0m    \e[0m\e[0m\e[1m\e[35m^\e[0m\e[0m   \e[0m\e[0m-\e[0m
+\e[0m  \e[0m\e[0m\e[1m| \e[0m\e[0m    \e[0m\e[0m\e[1m\e[35m|\e[0m
+\e[0m  \e[0m\e[0m\e[1m| \e[0m\e[0m    \e[0m\e[0m\e[1m\e[4munused\e[0m
+
+");
+}
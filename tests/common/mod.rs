@@ -0,0 +1,63 @@
+// Shared fixtures for the integration tests. Not every test file uses every helper.
+#![allow(dead_code)]
+
+use codemap::{ CodeMap, Span };
+use codemap_diagnostic::{ Applicability, Diagnostic, Emitter, Level, SpanLabel, SpanStyle, Style,
+                          StyledString, SubDiagnostic, Suggestion };
+
+/// Creates a code map holding `source` as `test.rs`, returning the span of the whole file.
+pub fn code_map(source: &str) -> (CodeMap, Span) {
+    let mut code_map = CodeMap::new();
+    let span = code_map.add_file("test.rs".to_owned(), source.to_owned()).span;
+    (code_map, span)
+}
+
+/// A diagnostic with no code, spans, children or suggestions. Use struct update syntax to fill
+/// in the rest.
+pub fn diagnostic(level: Level, message: &str) -> Diagnostic {
+    Diagnostic {
+        level,
+        message: message.into(),
+        code: None,
+        spans: vec![],
+        children: vec![],
+        suggestions: vec![],
+    }
+}
+
+/// A sub-diagnostic with the given spans.
+pub fn child(level: Level, message: &str, spans: Vec<SpanLabel>) -> SubDiagnostic {
+    SubDiagnostic { level, message: message.into(), spans }
+}
+
+/// A machine-applicable suggestion with the message "try".
+pub fn suggestion(span: Span, replacement: &str) -> Suggestion {
+    Suggestion {
+        span,
+        replacement: replacement.to_owned(),
+        message: "try".into(),
+        applicability: Applicability::MachineApplicable,
+    }
+}
+
+pub fn primary(span: Span, label: Option<&str>) -> SpanLabel {
+    SpanLabel { span, style: SpanStyle::Primary, label: label.map(|l| l.to_owned()) }
+}
+
+pub fn secondary(span: Span, label: Option<&str>) -> SpanLabel {
+    SpanLabel { span, style: SpanStyle::Secondary, label: label.map(|l| l.to_owned()) }
+}
+
+pub fn styled(text: &str, style: Style) -> StyledString {
+    StyledString { text: text.to_owned(), style }
+}
+
+/// Emits one group through an `Emitter` writing to a `Vec<u8>`, after passing it through
+/// `configure`, and returns the output.
+pub fn emit<F>(code_map: Option<&CodeMap>, diagnostics: &[Diagnostic], configure: F) -> String
+    where F: for<'a> FnOnce(Emitter<'a>) -> Emitter<'a>
+{
+    let mut out = Vec::new();
+    configure(Emitter::vec(&mut out, code_map)).emit(diagnostics);
+    String::from_utf8(out).unwrap()
+}
@@ -0,0 +1,120 @@
+extern crate codemap;
+extern crate codemap_diagnostic;
+
+mod common;
+
+use codemap_diagnostic::{ Diagnostic, Emitter, Level };
+use common::{ code_map, diagnostic, emit, primary, secondary };
+
+const SOURCE: &str = "\
+fn main() {
+    let a = 1;
+    let b = 2;
+    let c = 3;
+    let d = 4;
+    let e = 5;
+    let f = 6;
+    let g = 7;
+}
+";
+
+fn render<F>(lines: &[usize], configure: F) -> String
+    where F: for<'a> FnOnce(Emitter<'a>) -> Emitter<'a>
+{
+    let (code_map, file_span) = code_map(SOURCE);
+    let file = code_map.find_file(file_span.low());
+
+    let spans = lines.iter().enumerate().map(|(i, &line)| {
+        let span = file.line_span(line).subspan(8, 9);
+        if i == 0 { primary(span, None) } else { secondary(span, None) }
+    }).collect();
+
+    let d = Diagnostic { spans, ..diagnostic(Level::Error, "test") };
+    emit(Some(&code_map), &[d], configure)
+}
+
+#[test]
+fn default_gap() {
+    assert_eq!(render(&[1, 3, 7], |e| e), "\
+error: test
+ --> test.rs:2:9
+  |
+2 |     let a = 1;
+  |         ^
+3 |     let b = 2;
+4 |     let c = 3;
+  |         -
+...
+8 |     let g = 7;
+  |         -
+
+");
+}
+
+#[test]
+fn context() {
+    assert_eq!(render(&[2, 7], |e| e.context_lines(1)), "\
+error: test
+ --> test.rs:3:9
+  |
+2 |     let a = 1;
+3 |     let b = 2;
+  |         ^
+4 |     let c = 3;
+...
+7 |     let f = 6;
+8 |     let g = 7;
+  |         -
+9 | }
+
+");
+}
+
+#[test]
+fn context_overlapping() {
+    assert_eq!(render(&[2, 4], |e| e.context_lines(2)), "\
+error: test
+ --> test.rs:3:9
+  |
+1 | fn main() {
+2 |     let a = 1;
+3 |     let b = 2;
+  |         ^
+4 |     let c = 3;
+5 |     let d = 4;
+  |         -
+6 |     let e = 5;
+7 |     let f = 6;
+
+");
+}
+
+#[test]
+fn gap_threshold() {
+    assert_eq!(render(&[1, 7], |e| e.gap_threshold(5)), "\
+error: test
+ --> test.rs:2:9
+  |
+2 |     let a = 1;
+  |         ^
+3 |     let b = 2;
+4 |     let c = 3;
+5 |     let d = 4;
+6 |     let e = 5;
+7 |     let f = 6;
+8 |     let g = 7;
+  |         -
+
+");
+    assert_eq!(render(&[1, 3], |e| e.gap_threshold(0)), "\
+error: test
+ --> test.rs:2:9
+  |
+2 |     let a = 1;
+  |         ^
+...
+4 |     let c = 3;
+  |         -
+
+");
+}
@@ -0,0 +1,170 @@
+extern crate codemap;
+extern crate codemap_diagnostic;
+
+mod common;
+
+use codemap::{ CodeMap, Span };
+use codemap_diagnostic::{ DeferredEmitter, Diagnostic, Emit, Emitter, Handler, Level };
+use common::{ child, code_map, diagnostic, primary, secondary };
+
+fn located(level: Level, code: Option<&str>, span: Option<Span>) -> Diagnostic {
+    Diagnostic {
+        code: code.map(|c| c.to_owned()),
+        spans: span.into_iter().map(|span| primary(span, None)).collect(),
+        ..diagnostic(level, "test")
+    }
+}
+
+#[test]
+fn sorted() {
+    let mut codemap = CodeMap::new();
+    let b = codemap.add_file("b.rs".to_owned(), "x\ny\n".to_owned()).span;
+    let a = codemap.add_file("a.rs".to_owned(), "x\ny\n".to_owned()).span;
+
+    let mut out = Vec::new();
+    {
+        let emitter = Emitter::vec(&mut out, Some(&codemap)).short_message(true);
+        let handler = Handler::new(Box::new(emitter));
+        let mut deferred = DeferredEmitter::new(Box::new(handler), Some(&codemap));
+        deferred.emit(&[located(Level::Warning, None, None)]);
+        deferred.emit(&[located(Level::Error, None, Some(b.subspan(2, 3)))]);
+        deferred.emit(&[located(Level::Warning, Some("W2"), Some(a.subspan(2, 3)))]);
+        deferred.emit(&[located(Level::Error, None, Some(b.subspan(0, 1)))]);
+        deferred.emit(&[located(Level::Warning, Some("W1"), Some(a.subspan(2, 3)))]);
+        deferred.emit(&[located(Level::Error, None, Some(a.subspan(2, 3)))]);
+        deferred.emit(&[located(Level::Error, None, None)]);
+        Emit::finish(&mut deferred).unwrap();
+    }
+    assert_eq!(String::from_utf8(out).unwrap(), "\
+a.rs:2:1: error: test
+a.rs:2:1: warning[W1]: test
+a.rs:2:1: warning[W2]: test
+b.rs:1:1: error: test
+b.rs:2:1: error: test
+error: test
+warning: test
+error: aborting due to 4 previous errors; 3 warnings emitted
+");
+}
+
+/// The handler inside the deferred emitter sees the groups in sorted order, so the errors that
+/// fit in the limit don't depend on the order they were emitted in.
+#[test]
+fn sorted_error_limit() {
+    let (code_map, span) = code_map("a\nb\nc\n");
+    let groups = [
+        located(Level::Error, None, Some(span.subspan(4, 5))),
+        located(Level::Error, None, Some(span.subspan(0, 1))),
+        located(Level::Error, None, Some(span.subspan(2, 3))),
+    ];
+
+    let render = |groups: &mut dyn Iterator<Item = &Diagnostic>| {
+        let mut out = Vec::new();
+        let emitter = Emitter::vec(&mut out, Some(&code_map)).short_message(true);
+        let mut handler = Handler::new(Box::new(emitter)).error_limit(Some(2));
+        {
+            let mut deferred = DeferredEmitter::new(Box::new(&mut handler), Some(&code_map));
+            for group in groups {
+                deferred.emit(std::slice::from_ref(group));
+            }
+            Emit::finish(&mut deferred).unwrap();
+        }
+        assert_eq!(handler.error_count(), 2);
+        drop(handler);
+        String::from_utf8(out).unwrap()
+    };
+
+    let expected = "\
+test.rs:1:1: error: test
+test.rs:2:1: error: test
+note: too many errors, stopping
+error: aborting due to 2 previous errors
+";
+    assert_eq!(render(&mut groups.iter()), expected);
+    assert_eq!(render(&mut groups.iter().rev()), expected);
+}
+
+#[test]
+fn flush() {
+    let mut out = Vec::new();
+    {
+        let mut deferred = DeferredEmitter::new(Box::new(Emitter::vec(&mut out, None)), None);
+        deferred.emit(&[located(Level::Warning, None, None)]);
+        deferred.emit(&[located(Level::Error, None, None)]);
+        deferred.flush();
+        deferred.emit(&[located(Level::Note, None, None)]);
+        deferred.emit(&[located(Level::Help, None, None)]);
+        deferred.emit(&[located(Level::Bug, None, None)]);
+        deferred.flush();
+    }
+    assert_eq!(String::from_utf8(out).unwrap(), "\
+error: test
+
+warning: test
+
+error: internal compiler error: test
+
+note: test
+
+help: test
+
+");
+}
+
+#[test]
+fn tiebreak() {
+    let (code_map, span) = code_map("let x = y;\n");
+    let headline = located(Level::Error, Some("E1"), Some(span.subspan(8, 9)));
+    let a = Diagnostic {
+        spans: vec![primary(span.subspan(8, 9), None), secondary(span.subspan(4, 5), Some("a"))],
+        ..headline.clone()
+    };
+    let b = Diagnostic {
+        children: vec![child(Level::Note, "b", vec![])],
+        ..headline.clone()
+    };
+    let c = Diagnostic {
+        children: vec![child(Level::Note, "c", vec![])],
+        ..headline.clone()
+    };
+
+    let render = |groups: &[&[Diagnostic]]| {
+        let mut out = Vec::new();
+        {
+            let emitter = Emitter::vec(&mut out, Some(&code_map));
+            let mut deferred = DeferredEmitter::new(Box::new(emitter), Some(&code_map));
+            for group in groups {
+                deferred.emit(group);
+            }
+        }
+        String::from_utf8(out).unwrap()
+    };
+
+    // Every group has the same headline, so only the rest of the contents orders them
+    let groups = [
+        vec![a],
+        vec![b.clone()],
+        vec![c.clone()],
+        vec![headline.clone(), c],
+        vec![headline, b],
+    ];
+    let forward: Vec<&[Diagnostic]> = groups.iter().map(|g| &g[..]).collect();
+    let backward: Vec<&[Diagnostic]> = groups.iter().rev().map(|g| &g[..]).collect();
+    assert_eq!(render(&forward), render(&backward));
+}
+
+#[test]
+fn flush_on_drop() {
+    let mut out = Vec::new();
+    {
+        let mut deferred = DeferredEmitter::new(Box::new(Emitter::vec(&mut out, None)), None);
+        deferred.emit(&[located(Level::Warning, None, None)]);
+        deferred.emit(&[located(Level::Error, None, None)]);
+    }
+    assert_eq!(String::from_utf8(out).unwrap(), "\
+error: test
+
+warning: test
+
+");
+}
@@ -0,0 +1,256 @@
+extern crate codemap;
+extern crate codemap_diagnostic;
+
+mod common;
+
+use codemap_diagnostic::{ Diagnostic, Emitter, Level, SpanLabel, SpanStyle };
+use common::{ code_map, diagnostic, emit };
+
+fn render(source: &str, labels: &[(usize, usize, SpanStyle, &str)]) -> String {
+    render_with(source, labels, |e| e)
+}
+
+fn render_with<F>(source: &str, labels: &[(usize, usize, SpanStyle, &str)], configure: F) -> String
+    where F: for<'a> FnOnce(Emitter<'a>) -> Emitter<'a>
+{
+    let (code_map, file_span) = code_map(source);
+    let spans = labels.iter().map(|&(start, end, style, label)| {
+        SpanLabel {
+            span: file_span.subspan(start as u64, end as u64),
+            style,
+            label: Some(label.to_owned()),
+        }
+    }).collect();
+
+    let d = Diagnostic { spans, ..diagnostic(Level::Error, "test") };
+    emit(Some(&code_map), &[d], configure)
+}
+
+fn byte_range(source: &str, needle: &str) -> (usize, usize) {
+    let start = source.find(needle).unwrap();
+    (start, start + needle.len())
+}
+
+#[test]
+fn wide_characters() {
+    let source = "let 名前 = 値 + x;";
+    let (a, b) = byte_range(source, "名前");
+    let (c, d) = byte_range(source, "値");
+    let (e, f) = byte_range(source, "x");
+    let out = render(source, &[
+        (a, b, SpanStyle::Primary, "first"),
+        (c, d, SpanStyle::Secondary, "second"),
+        (e, f, SpanStyle::Secondary, "third"),
+    ]);
+    assert_eq!(out, "\
+error: test
+ --> test.rs:1:5
+  |
+1 | let 名前 = 値 + x;
+  |     ^^^^   --   - third
+  |     |      |
+  |     |      second
+  |     first
+
+");
+}
+
+#[test]
+fn emoji() {
+    let source = "say(\"🎉🎉\", bad)";
+    let (a, b) = byte_range(source, "\"🎉🎉\"");
+    let (c, d) = byte_range(source, "bad");
+    let out = render(source, &[
+        (a, b, SpanStyle::Secondary, "string"),
+        (c, d, SpanStyle::Primary, "unknown name"),
+    ]);
+    assert_eq!(out, "\
+error: test
+ --> test.rs:1:11
+  |
+1 | say(\"🎉🎉\", bad)
+  |     ------  ^^^ unknown name
+  |     |
+  |     string
+
+");
+}
+
+#[test]
+fn combining_marks() {
+    let source = "cafe\u{301} = re\u{301}sume\u{301} + 1";
+    let (a, b) = byte_range(source, "cafe\u{301}");
+    let (c, d) = byte_range(source, "re\u{301}sume\u{301}");
+    let (e, f) = byte_range(source, "1");
+    let out = render(source, &[
+        (a, b, SpanStyle::Secondary, "name"),
+        (c, d, SpanStyle::Primary, "value"),
+        (e, f, SpanStyle::Secondary, "one"),
+    ]);
+    assert_eq!(out, "\
+error: test
+ --> test.rs:1:9
+  |
+1 | cafe\u{301} = re\u{301}sume\u{301} + 1
+  | ----   ^^^^^^   - one
+  | |      |
+  | |      value
+  | name
+
+");
+}
+
+#[test]
+fn wide_label() {
+    let source = "数 + 数";
+    let out = render(source, &[
+        (0, 3, SpanStyle::Primary, "左"),
+        (6, 9, SpanStyle::Secondary, "右"),
+    ]);
+    assert_eq!(out, "\
+error: test
+ --> test.rs:1:1
+  |
+1 | 数 + 数
+  | ^^   -- 右
+  | |
+  | 左
+
+");
+}
+
+#[test]
+fn tabs() {
+    let source = "all:\n\tcc -o\tout main.c\n";
+    let (a, b) = byte_range(source, "cc");
+    let (c, d) = byte_range(source, "out");
+    let out = render(source, &[
+        (a, b, SpanStyle::Primary, "compiler"),
+        (c, d, SpanStyle::Secondary, "output"),
+    ]);
+    assert_eq!(out, "\
+error: test
+ --> test.rs:2:2
+  |
+2 |     cc -o   out main.c
+  |     ^^      --- output
+  |     |
+  |     compiler
+
+");
+}
+
+#[test]
+fn tab_width() {
+    let source = "x\ty\t\tz";
+    let (a, b) = byte_range(source, "z");
+    let out = render_with(source, &[(a, b, SpanStyle::Primary, "here")], |e| e.tab_width(8));
+    assert_eq!(out, "\
+error: test
+ --> test.rs:1:6
+  |
+1 | x       y               z
+  |                         ^ here
+
+");
+
+    let out = render_with(source, &[(a, b, SpanStyle::Primary, "here")], |e| e.tab_width(0));
+    assert_eq!(out, "\
+error: test
+ --> test.rs:1:6
+  |
+1 | xyz
+  |   ^ here
+
+");
+}
+
+#[test]
+fn truncate_long_line() {
+    let source = format!("let x = [{}]; let y = z + 1; // {}", "1, ".repeat(30), "comment ".repeat(10));
+    let (a, b) = byte_range(&source, "z");
+    let (c, d) = byte_range(&source, "y");
+    let out = render_with(&source, &[
+        (a, b, SpanStyle::Primary, "not found"),
+        (c, d, SpanStyle::Secondary, "binding"),
+    ], |e| e.max_width(Some(50)));
+    assert_eq!(out, "\
+error: test
+ --> test.rs:1:111
+  |
+1 | ..., 1, ]; let y = z + 1; // comment commen...
+  |                -   ^ not found
+  |                |
+  |                binding
+
+");
+}
+
+#[test]
+fn truncate_multiline() {
+    let source = format!("{}foo(\n{}bar)", "x".repeat(60), " ".repeat(60));
+    let (a, _) = byte_range(&source, "foo");
+    let (_, b) = byte_range(&source, "bar)");
+    let out = render_with(&source, &[(a, b, SpanStyle::Primary, "call")], |e| e.max_width(Some(40)));
+    assert_eq!(out, "\
+error: test
+ --> test.rs:1:61
+  |
+1 |   ...xxxxxxxxxxxxxxxxxxxxxxfoo(
+  |  __________________________^
+2 | | ...                      bar)
+  | |_____________________________^ call
+
+");
+}
+
+#[test]
+fn truncate_wide_spans() {
+    let source = format!("{}foo(){}bar(){}", "a".repeat(80), "b".repeat(30), "c".repeat(30));
+    let (a, b) = byte_range(&source, "foo");
+    let (c, d) = byte_range(&source, "bar");
+    let out = render_with(&source, &[
+        (a, b, SpanStyle::Primary, "first"),
+        (c, d, SpanStyle::Secondary, "second"),
+    ], |e| e.max_width(Some(40)));
+    assert_eq!(out, "\
+error: test
+ --> test.rs:1:81
+  |
+1 | ...aaaaaaaaaafoo()bbbbbbbbbbbbbbb...
+  |              ^^^ first           --- second
+
+");
+
+    let out = render_with(&source, &[
+        (a, b, SpanStyle::Primary, "first"),
+        (c, d, SpanStyle::Secondary, "second label that is too long to fit"),
+    ], |e| e.max_width(Some(70)));
+    assert_eq!(out, "\
+error: test
+ --> test.rs:1:81
+  |
+1 | ...aaaaaaafoo()bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbar()ccccccccccccc...
+  |           ^^^ first                          --- second label t...
+
+");
+}
+
+#[test]
+fn truncate_keeps_primary_span() {
+    let source = format!("foo = {};bar", "x".repeat(100));
+    let (a, b) = byte_range(&source, "bar");
+    let (c, d) = byte_range(&source, "foo");
+    let out = render_with(&source, &[
+        (a, b, SpanStyle::Primary, "the actual error"),
+        (c, d, SpanStyle::Secondary, "defined here"),
+    ], |e| e.max_width(Some(60)));
+    assert_eq!(out, "\
+error: test
+ --> test.rs:1:108
+  |
+1 | ...xxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxx;bar
+  | --- defined here                    ^^^ the actual error
+
+");
+}
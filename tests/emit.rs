@@ -0,0 +1,55 @@
+extern crate codemap;
+extern crate codemap_diagnostic;
+
+mod common;
+
+use std::io;
+use codemap::CodeMap;
+use codemap_diagnostic::{ Diagnostic, Emit, Emitter, JsonEmitter, Level, SarifEmitter };
+use common::{ code_map, diagnostic, primary };
+
+/// Creates the emitter for an output format chosen at runtime, as a compiler driver would.
+fn emitter<'a>(format: &str, out: &'a mut Vec<u8>, code_map: &'a CodeMap) -> Box<dyn Emit + 'a> {
+    match format {
+        "human" => Box::new(Emitter::vec(out, Some(code_map))),
+        "json" => Box::new(JsonEmitter::vec(out, Some(code_map))),
+        "sarif" => Box::new(SarifEmitter::vec(out, "tool", Some(code_map))),
+        _ => unreachable!(),
+    }
+}
+
+fn emit_as(format: &str, code_map: &CodeMap, diagnostics: &[Diagnostic]) -> io::Result<String> {
+    let mut out = Vec::new();
+    {
+        let mut emitter = emitter(format, &mut out, code_map);
+        emitter.emit(diagnostics)?;
+        emitter.finish()?;
+    }
+    Ok(String::from_utf8(out).unwrap())
+}
+
+#[test]
+fn dyn_emit() {
+    let (code_map, span) = code_map("let x = y;\n");
+    let diagnostics = [Diagnostic {
+        spans: vec![primary(span.subspan(8, 9), Some("not found"))],
+        ..diagnostic(Level::Error, "cannot find value `y`")
+    }];
+
+    assert_eq!(emit_as("human", &code_map, &diagnostics).unwrap(), "\
+error: cannot find value `y`
+ --> test.rs:1:9
+  |
+1 | let x = y;
+  |         ^ not found
+
+");
+
+    let json = emit_as("json", &code_map, &diagnostics).unwrap();
+    assert!(json.starts_with(r#"{"$message_type":"diagnostic","message":"cannot find value `y`""#));
+    assert_eq!(json.lines().count(), 1);
+
+    let sarif = emit_as("sarif", &code_map, &diagnostics).unwrap();
+    assert!(sarif.starts_with(r#"{"$schema":"https://json.schemastore.org/sarif-2.1.0.json""#));
+    assert!(sarif.contains(r#""message":{"text":"cannot find value `y`"}"#));
+}
@@ -0,0 +1,144 @@
+extern crate codemap;
+extern crate codemap_diagnostic;
+
+mod common;
+
+use codemap::CodeMap;
+use codemap_diagnostic::{ Applicability, Diagnostic, Level, Suggestion };
+use codemap_diagnostic::fix::apply_suggestions;
+use common::{ code_map, diagnostic, suggestion };
+
+fn with_suggestions(suggestions: Vec<Suggestion>) -> Diagnostic {
+    Diagnostic { suggestions, ..diagnostic(Level::Error, "test") }
+}
+
+fn machine_applicable(a: Applicability) -> bool {
+    a == Applicability::MachineApplicable
+}
+
+#[test]
+fn source_order() {
+    let (code_map, span) = code_map("let x = fo + ba;");
+    let d = with_suggestions(vec![
+        suggestion(span.subspan(13, 15), "bar"),
+        suggestion(span.subspan(8, 10), "foo"),
+        suggestion(span.subspan(4, 4), "mut "),
+    ]);
+    let patches = apply_suggestions(&code_map, &[d], machine_applicable).unwrap();
+    assert_eq!(patches.len(), 1);
+    assert_eq!(patches[0].content, "let mut x = foo + bar;");
+    let replacements: Vec<_> = patches[0].suggestions.iter().map(|s| &s.replacement[..]).collect();
+    assert_eq!(replacements, ["mut ", "foo", "bar"]);
+}
+
+#[test]
+fn adjacent() {
+    let (code_map, span) = code_map("ab");
+    let d = with_suggestions(vec![
+        suggestion(span.subspan(0, 1), "x"),
+        suggestion(span.subspan(1, 1), "y"),
+        suggestion(span.subspan(1, 2), "z"),
+    ]);
+    let patches = apply_suggestions(&code_map, &[d], machine_applicable).unwrap();
+    assert_eq!(patches[0].content, "xyz");
+}
+
+#[test]
+fn overlap() {
+    let (code_map, span) = code_map("let x = foo;\nlet y = bar;\n");
+    let d = with_suggestions(vec![
+        suggestion(span.subspan(21, 24), "baz"),
+        suggestion(span.subspan(17, 22), "z ="),
+    ]);
+    let err = apply_suggestions(&code_map, &[d], machine_applicable).unwrap_err();
+    assert_eq!(err.first, span.subspan(17, 22));
+    assert_eq!(err.second, span.subspan(21, 24));
+    assert_eq!(err.to_string(), "overlapping suggestions in test.rs at 2:5 and 2:9");
+}
+
+#[test]
+fn insertions_at_same_position() {
+    let (code_map, span) = code_map("x");
+    let d = with_suggestions(vec![
+        suggestion(span.subspan(0, 0), "a"),
+        suggestion(span.subspan(0, 0), "b"),
+    ]);
+    let err = apply_suggestions(&code_map, &[d], machine_applicable).unwrap_err();
+    assert_eq!(err.to_string(), "overlapping suggestions in test.rs at 1:1 and 1:1");
+}
+
+#[test]
+fn duplicates() {
+    let (code_map, span) = code_map("let x = fo;");
+    let fix = suggestion(span.subspan(8, 10), "foo");
+    let diagnostics = [
+        with_suggestions(vec![fix.clone()]),
+        with_suggestions(vec![fix.clone(), suggestion(span.subspan(0, 0), "// x\n")]),
+    ];
+    let patches = apply_suggestions(&code_map, &diagnostics, machine_applicable).unwrap();
+    assert_eq!(patches[0].suggestions.len(), 2);
+    assert_eq!(patches[0].content, "// x\nlet x = foo;");
+
+    let conflicting = with_suggestions(vec![suggestion(span.subspan(8, 10), "fob")]);
+    assert!(apply_suggestions(&code_map, &[with_suggestions(vec![fix]), conflicting],
+                              machine_applicable).is_err());
+}
+
+#[test]
+fn multiple_files() {
+    let mut code_map = CodeMap::new();
+    let a = code_map.add_file("a.rs".to_owned(), "a".to_owned()).span;
+    let b = code_map.add_file("b.rs".to_owned(), "b".to_owned()).span;
+    let diagnostics = [
+        with_suggestions(vec![suggestion(b.subspan(0, 1), "B")]),
+        with_suggestions(vec![suggestion(a.subspan(0, 1), "A"), suggestion(b.subspan(1, 1), "!")]),
+    ];
+    let patches = apply_suggestions(&code_map, &diagnostics, machine_applicable).unwrap();
+    let files: Vec<_> = patches.iter().map(|p| (p.file.name(), &p.content[..])).collect();
+    assert_eq!(files, [("b.rs", "B!"), ("a.rs", "A")]);
+}
+
+#[test]
+fn applicability_filter() {
+    let (code_map, span) = code_map("fo(ba)");
+    let d = with_suggestions(vec![
+        suggestion(span.subspan(0, 2), "foo"),
+        Suggestion {
+            applicability: Applicability::MaybeIncorrect,
+            ..suggestion(span.subspan(3, 5), "bar")
+        },
+        Suggestion {
+            applicability: Applicability::HasPlaceholders,
+            ..suggestion(span.subspan(3, 5), "(...)")
+        },
+    ]);
+    let patches = apply_suggestions(&code_map, std::slice::from_ref(&d), machine_applicable)
+        .unwrap();
+    assert_eq!(patches[0].content, "foo(ba)");
+
+    let patches = apply_suggestions(&code_map, std::slice::from_ref(&d), |a| {
+        a != Applicability::HasPlaceholders
+    }).unwrap();
+    assert_eq!(patches[0].content, "foo(bar)");
+
+    assert_eq!(apply_suggestions(&code_map, &[d], |_| false).unwrap(), vec![]);
+}
+
+#[test]
+fn multibyte() {
+    let (code_map, span) = code_map("let π = «ä»;");
+    let d = with_suggestions(vec![
+        suggestion(span.subspan(4, 6), "pi"),
+        suggestion(span.subspan(11, 13), "ö"),
+    ]);
+    let patches = apply_suggestions(&code_map, std::slice::from_ref(&d), machine_applicable)
+        .unwrap();
+    assert_eq!(patches[0].content, "let pi = «ö»;");
+
+    let d = with_suggestions(vec![
+        suggestion(span.subspan(9, 15), "x"),
+        suggestion(span.subspan(11, 13), "ö"),
+    ]);
+    let err = apply_suggestions(&code_map, &[d], machine_applicable).unwrap_err();
+    assert_eq!(err.to_string(), "overlapping suggestions in test.rs at 1:9 and 1:10");
+}
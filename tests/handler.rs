@@ -0,0 +1,145 @@
+extern crate codemap;
+extern crate codemap_diagnostic;
+
+mod common;
+
+use codemap_diagnostic::{ Diagnostic, Emitter, Handler, Level, SarifEmitter };
+use common::{ child, diagnostic };
+
+#[test]
+fn counts_and_summary() {
+    let mut out = Vec::new();
+    {
+        let mut handler = Handler::new(Box::new(Emitter::vec(&mut out, None)));
+        handler.emit(&[diagnostic(Level::Error, "first"), diagnostic(Level::Warning, "second")]);
+        handler.emit(&[diagnostic(Level::Error, "third"), diagnostic(Level::Note, "fourth")]);
+        handler.emit(&[diagnostic(Level::Warning, "fifth")]);
+
+        assert_eq!(handler.error_count(), 2);
+        assert_eq!(handler.warning_count(), 2);
+        assert_eq!(handler.count(Level::Note), 1);
+        assert!(handler.has_errors());
+        handler.finish();
+    }
+    assert_eq!(String::from_utf8(out).unwrap(), "\
+error: first
+warning: second
+
+error: third
+note: fourth
+
+warning: fifth
+
+error: aborting due to 2 previous errors; 2 warnings emitted
+
+");
+}
+
+#[test]
+fn summary() {
+    let mut out = Vec::new();
+    let mut handler = Handler::new(Box::new(Emitter::vec(&mut out, None)));
+    assert_eq!(handler.summary(), None);
+    assert!(!handler.has_errors());
+
+    handler.emit(&[diagnostic(Level::Warning, "w")]);
+    assert_eq!(handler.summary(), Some(diagnostic(Level::Warning, "1 warning emitted")));
+
+    handler.emit(&[diagnostic(Level::Bug, "b")]);
+    assert_eq!(handler.summary(),
+               Some(diagnostic(Level::Error, "aborting due to previous error; 1 warning emitted")));
+}
+
+#[test]
+fn error_limit() {
+    let mut out = Vec::new();
+    {
+        let mut handler = Handler::new(Box::new(Emitter::vec(&mut out, None)))
+            .error_limit(Some(2));
+        handler.emit(&[diagnostic(Level::Error, "1"), diagnostic(Level::Warning, "2")]);
+        assert!(!handler.error_limit_reached());
+        handler.emit(&[diagnostic(Level::Error, "3"), diagnostic(Level::Error, "4")]);
+        assert!(handler.error_limit_reached());
+        handler.emit(&[diagnostic(Level::Warning, "5")]);
+        handler.emit(&[diagnostic(Level::Error, "6")]);
+        assert_eq!(handler.error_count(), 3);
+        assert_eq!(handler.warning_count(), 1);
+        handler.finish();
+    }
+    assert_eq!(String::from_utf8(out).unwrap(), "\
+error: 1
+warning: 2
+
+error: 3
+error: 4
+
+note: too many errors, stopping
+
+error: aborting due to 3 previous errors; 1 warning emitted
+
+");
+}
+
+#[test]
+fn error_limit_keeps_groups_whole() {
+    let mut out = Vec::new();
+    {
+        let mut handler = Handler::new(Box::new(Emitter::vec(&mut out, None)))
+            .error_limit(Some(1));
+        let error = Diagnostic {
+            children: vec![child(Level::Note, "attached", vec![])],
+            ..diagnostic(Level::Error, "first")
+        };
+        handler.emit(&[error, diagnostic(Level::Note, "related")]);
+        handler.emit(&[diagnostic(Level::Error, "second")]);
+    }
+    assert_eq!(String::from_utf8(out).unwrap(), "\
+error: first
+  |
+  = note: attached
+note: related
+
+note: too many errors, stopping
+
+");
+}
+
+#[test]
+fn dedup() {
+    let mut out = Vec::new();
+    {
+        let mut handler = Handler::new(Box::new(Emitter::vec(&mut out, None))).dedup(true);
+        handler.emit(&[diagnostic(Level::Error, "a"), diagnostic(Level::Error, "a")]);
+        handler.emit(&[diagnostic(Level::Error, "a"), diagnostic(Level::Warning, "a")]);
+        handler.emit(&[diagnostic(Level::Error, "b")]);
+        assert_eq!(handler.error_count(), 2);
+        assert_eq!(handler.warning_count(), 1);
+    }
+    assert_eq!(String::from_utf8(out).unwrap(), "\
+error: a
+
+warning: a
+
+error: b
+
+");
+}
+
+#[test]
+fn no_summary_in_sarif() {
+    let mut out = Vec::new();
+    {
+        let emitter = SarifEmitter::vec(&mut out, "tool", None);
+        let mut handler = Handler::new(Box::new(emitter)).error_limit(Some(1));
+        handler.emit(&[diagnostic(Level::Error, "first")]);
+        handler.emit(&[diagnostic(Level::Error, "second")]);
+        assert_eq!(handler.summary(),
+                   Some(diagnostic(Level::Error, "aborting due to previous error")));
+        handler.finish();
+    }
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains(r#""results":[{"level":"error","message":{"text":"first"},"#));
+    assert!(!out.contains("second"));
+    assert!(!out.contains("aborting"));
+    assert!(!out.contains("stopping"));
+}
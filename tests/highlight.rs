@@ -0,0 +1,97 @@
+extern crate codemap;
+extern crate codemap_diagnostic;
+
+mod common;
+
+use std::ops::Range;
+use codemap::File;
+use codemap_diagnostic::{ Diagnostic, Emitter, Highlighter, Level, Style, StyledString,
+                          SyntaxClass };
+use common::{ code_map, diagnostic, primary, styled };
+
+/// Highlights the keyword `let` and decimal numbers.
+struct Simple;
+
+impl Highlighter for Simple {
+    fn highlight_line(&self, file: &File, line: usize) -> Vec<(Range<usize>, SyntaxClass)> {
+        let source = file.source_line(line);
+        let mut ranges = vec![];
+        if let Some(start) = source.find("let") {
+            ranges.push((start..start + 3, SyntaxClass::Keyword));
+        }
+        for (i, c) in source.char_indices() {
+            if c.is_ascii_digit() {
+                ranges.push((i..i + 1, SyntaxClass::Number));
+            }
+        }
+        ranges
+    }
+}
+
+fn render_quoted_lines(source: &str, span: (u64, u64), max_width: Option<usize>) -> Vec<Vec<StyledString>> {
+    let (codemap, file_span) = code_map(source);
+    let d = Diagnostic {
+        spans: vec![primary(file_span.subspan(span.0, span.1), None)],
+        ..diagnostic(Level::Error, "test")
+    };
+
+    let emitter = Emitter::renderer(Some(&codemap))
+        .highlighter(Box::new(Simple))
+        .max_width(max_width);
+    let mut rendered = emitter.render(&[d]);
+    rendered.remove(0).1.into_iter().skip(3).step_by(2).collect()
+}
+
+#[test]
+fn highlight() {
+    let lines = render_quoted_lines("\tlet x = 12;", (5, 6), None);
+    assert_eq!(lines, vec![vec![
+        styled("1", Style::LineNumber),
+        styled(" ", Style::NoStyle),
+        styled("| ", Style::LineNumber),
+        styled("    ", Style::Quotation),
+        styled("let", Style::Syntax(SyntaxClass::Keyword)),
+        styled(" x = ", Style::Quotation),
+        styled("12", Style::Syntax(SyntaxClass::Number)),
+        styled(";", Style::Quotation),
+    ]]);
+}
+
+#[test]
+fn highlight_truncated() {
+    let source = format!("let x = 1{};", " + 1".repeat(20));
+    let lines = render_quoted_lines(&source, (4, 5), Some(20));
+    assert_eq!(lines, vec![vec![
+        styled("1", Style::LineNumber),
+        styled(" ", Style::NoStyle),
+        styled("| ", Style::LineNumber),
+        styled("let", Style::Syntax(SyntaxClass::Keyword)),
+        styled(" x = ", Style::Quotation),
+        styled("1", Style::Syntax(SyntaxClass::Number)),
+        styled(" + ", Style::Quotation),
+        styled("1", Style::Syntax(SyntaxClass::Number)),
+        styled("...", Style::LineNumber),
+    ]]);
+}
+
+#[test]
+fn backtick_labels() {
+    let (codemap, file_span) = code_map("let x = y;");
+    let d = Diagnostic {
+        spans: vec![primary(file_span.subspan(8, 9), Some("no `y` here"))],
+        ..diagnostic(Level::Error, "test")
+    };
+
+    let emitter = Emitter::renderer(Some(&codemap)).highlight_backticks(true);
+    let rendered = emitter.render(&[d]);
+    assert_eq!(rendered[0].1[4], vec![
+        styled("  ", Style::NoStyle),
+        styled("| ", Style::LineNumber),
+        styled("        ", Style::NoStyle),
+        styled("^", Style::UnderlinePrimary),
+        styled(" ", Style::NoStyle),
+        styled("no ", Style::LabelPrimary),
+        styled("`y`", Style::Highlight),
+        styled(" here", Style::LabelPrimary),
+    ]);
+}
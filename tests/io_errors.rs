@@ -0,0 +1,84 @@
+extern crate codemap;
+extern crate codemap_diagnostic;
+
+mod common;
+
+use std::io::{ self, Write };
+use codemap_diagnostic::{ ColorConfig, Emitter, Level };
+use common::diagnostic;
+
+/// The write end of a pipe or socket whose reader has gone away.
+struct ClosedPipe {
+    /// Accept writes and only fail on `flush`.
+    accept_writes: bool,
+}
+
+impl Write for ClosedPipe {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.accept_writes {
+            Ok(buf.len())
+        } else {
+            Err(io::Error::new(io::ErrorKind::BrokenPipe, "pipe closed"))
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::BrokenPipe, "pipe closed"))
+    }
+}
+
+#[test]
+fn try_emit_returns_write_error() {
+    let mut emitter = Emitter::new(Box::new(ClosedPipe { accept_writes: false }), None);
+    let err = emitter.try_emit(&[diagnostic(Level::Error, "test")]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+}
+
+#[test]
+fn try_emit_returns_flush_error() {
+    let mut emitter = Emitter::new(Box::new(ClosedPipe { accept_writes: true }), None);
+    let err = emitter.try_emit(&[diagnostic(Level::Error, "test")]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+}
+
+#[test]
+#[should_panic(expected = "failed to emit error")]
+fn emit_panics_on_write_error() {
+    let mut emitter = Emitter::new(Box::new(ClosedPipe { accept_writes: false }), None);
+    emitter.emit(&[diagnostic(Level::Error, "test")]);
+}
+
+/// Emits to stderr from a child process whose stderr is a pipe that the parent closes first,
+/// so that the buffered stderr output fails with `BrokenPipe`.
+#[test]
+#[cfg(unix)]
+fn try_emit_returns_stderr_error() {
+    use std::env;
+    use std::io::Read;
+    use std::process::{ Command, Stdio };
+
+    if env::var_os("CODEMAP_DIAGNOSTIC_CLOSED_STDERR").is_some() {
+        // Wait for the parent to close the pipe
+        io::stdin().read_to_end(&mut Vec::new()).unwrap();
+        let mut emitter = Emitter::stderr(ColorConfig::Never, None);
+        let result = emitter.try_emit(&[diagnostic(Level::Error, "test")]);
+        println!("result: {:?}", result.map_err(|e| e.kind()));
+        return;
+    }
+
+    let mut child = Command::new(env::current_exe().unwrap())
+        .args(["--exact", "try_emit_returns_stderr_error", "--nocapture", "--test-threads=1"])
+        .env("CODEMAP_DIAGNOSTIC_CLOSED_STDERR", "1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    drop(child.stderr.take());
+    drop(child.stdin.take());
+
+    let mut stdout = String::new();
+    child.stdout.take().unwrap().read_to_string(&mut stdout).unwrap();
+    child.wait().unwrap();
+    assert!(stdout.contains("result: Err(BrokenPipe)"), "{}", stdout);
+}
@@ -0,0 +1,108 @@
+extern crate codemap;
+extern crate codemap_diagnostic;
+
+mod common;
+
+use codemap::CodeMap;
+use codemap_diagnostic::{ Applicability, Diagnostic, JsonEmitter, Level, Suggestion };
+use common::{ child, code_map, diagnostic, primary, secondary, suggestion };
+
+fn emit_json(code_map: Option<&CodeMap>, diagnostics: &[Diagnostic]) -> String {
+    let mut out = Vec::new();
+    JsonEmitter::vec(&mut out, code_map).emit(diagnostics);
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn escaping() {
+    let d = diagnostic(Level::Warning, "\"quoted\" C:\\path\ttab\r\n\u{1}\u{1f} naïve → 😀");
+    assert_eq!(emit_json(None, &[d]), concat!(
+        r#"{"$message_type":"diagnostic""#,
+        r#","message":"\"quoted\" C:\\path\ttab\r\n\u0001\u001f naïve → 😀","code":null"#,
+        r#","level":"warning","spans":[],"children":[]"#,
+        r#","rendered":"warning: \"quoted\" C:\\path\ttab\r\n\u0001\u001f naïve → 😀\n\n"}"#,
+        "\n"));
+}
+
+#[test]
+fn no_code_map() {
+    // The spans are dropped when there is no code map to resolve them with.
+    let (_code_map, span) = code_map("let x = 1;");
+    let d = Diagnostic {
+        code: Some("W000".to_owned()),
+        spans: vec![primary(span.subspan(4, 5), Some("unused"))],
+        children: vec![child(Level::Note, "defined here", vec![primary(span.subspan(4, 5), None)])],
+        suggestions: vec![suggestion(span.subspan(4, 5), "_x")],
+        ..diagnostic(Level::Warning, "unused variable")
+    };
+    assert_eq!(emit_json(None, &[d]), concat!(
+        r#"{"$message_type":"diagnostic","message":"unused variable","code":{"code":"W000""#,
+        r#","explanation":null},"level":"warning","spans":[]"#,
+        r#","children":[{"message":"defined here","code":null,"level":"note","spans":[]"#,
+        r#","children":[],"rendered":null},{"message":"try","code":null,"level":"help","spans":[]"#,
+        r#","children":[],"rendered":null}]"#,
+        r#","rendered":"warning[W000]: unused variable\n  |\nnote: defined here\nhelp: try\n\n"}"#,
+        "\n"));
+}
+
+#[test]
+fn spans() {
+    let (code_map, span) = code_map("fn f() {\n    «x»\n}\n");
+    let d = Diagnostic {
+        code: Some("E0001".to_owned()),
+        spans: vec![
+            primary(span.subspan(0, 16), Some("multi\nline")),
+            secondary(span.subspan(15, 16), None),
+        ],
+        ..diagnostic(Level::Error, "test")
+    };
+    assert_eq!(emit_json(Some(&code_map), &[d]), concat!(
+        r#"{"$message_type":"diagnostic","message":"test","code":{"code":"E0001""#,
+        r#","explanation":null},"level":"error","spans":[{"file_name":"test.rs","byte_start":0"#,
+        r#","byte_end":16,"line_start":1,"line_end":2,"column_start":1,"column_end":7"#,
+        r#","is_primary":true,"text":[{"text":"fn f() {","highlight_start":1"#,
+        r#","highlight_end":9},{"text":"    «x»","highlight_start":1,"highlight_end":7}]"#,
+        r#","label":"multi\nline","suggested_replacement":null,"suggestion_applicability":null"#,
+        r#","expansion":null},{"file_name":"test.rs","byte_start":15,"byte_end":16,"line_start":2"#,
+        r#","line_end":2,"column_start":6,"column_end":7,"is_primary":false"#,
+        r#","text":[{"text":"    «x»","highlight_start":6,"highlight_end":7}],"label":null"#,
+        r#","suggested_replacement":null,"suggestion_applicability":null,"expansion":null}]"#,
+        r#","children":[]"#,
+        r#","rendered":"error[E0001]: test\n --> test.rs:1:1\n  |\n1 | / fn f() {\n"#,
+        r#"2 | |     «x»\n  | |      ^\n  | |______|\n  |        multi\nline\n\n"}"#,
+        "\n"));
+}
+
+#[test]
+fn suggestions() {
+    let (code_map, span) = code_map("let x = fo;\n");
+    let d = Diagnostic {
+        children: vec![child(Level::Help, "see docs", vec![])],
+        suggestions: vec![
+            suggestion(span.subspan(8, 10), "foo"),
+            Suggestion {
+                applicability: Applicability::HasPlaceholders,
+                ..suggestion(span.subspan(10, 10), "(\"...\")")
+            },
+        ],
+        ..diagnostic(Level::Error, "test")
+    };
+    assert_eq!(emit_json(Some(&code_map), &[d]), concat!(
+        r#"{"$message_type":"diagnostic","message":"test","code":null,"level":"error","spans":[]"#,
+        r#","children":[{"message":"see docs","code":null,"level":"help","spans":[],"children":[]"#,
+        r#","rendered":null},{"message":"try","code":null,"level":"help""#,
+        r#","spans":[{"file_name":"test.rs","byte_start":8,"byte_end":10,"line_start":1"#,
+        r#","line_end":1,"column_start":9,"column_end":11,"is_primary":true"#,
+        r#","text":[{"text":"let x = fo;","highlight_start":9,"highlight_end":11}],"label":null"#,
+        r#","suggested_replacement":"foo","suggestion_applicability":"MachineApplicable""#,
+        r#","expansion":null}],"children":[],"rendered":null},{"message":"try","code":null"#,
+        r#","level":"help","spans":[{"file_name":"test.rs","byte_start":10,"byte_end":10"#,
+        r#","line_start":1,"line_end":1,"column_start":11,"column_end":11,"is_primary":true"#,
+        r#","text":[{"text":"let x = fo;","highlight_start":11,"highlight_end":11}],"label":null"#,
+        r#","suggested_replacement":"(\"...\")","suggestion_applicability":"HasPlaceholders""#,
+        r#","expansion":null}],"children":[],"rendered":null}]"#,
+        r#","rendered":"error: test\n  |\n  = help: see docs\nhelp: try\n  |\n"#,
+        r#"1 | let x = foo;\n  |         ~~~\nhelp: try\n  |\n"#,
+        r#"1 | let x = fo(\"...\");\n  |           +++++++\n\n"}"#,
+        "\n"));
+}
@@ -0,0 +1,118 @@
+extern crate codemap;
+extern crate codemap_diagnostic;
+
+mod common;
+
+use codemap_diagnostic::{ Diagnostic, Emitter, Handler, Level, LintLevel, LintPolicy };
+use common::{ child, diagnostic };
+
+fn warning(code: Option<&str>) -> Diagnostic {
+    Diagnostic { code: code.map(|c| c.to_owned()), ..diagnostic(Level::Warning, "unused") }
+}
+
+fn denied(code: Option<&str>, note_message: &str) -> Diagnostic {
+    let mut d = warning(code);
+    d.level = Level::Error;
+    d.children.push(child(Level::Note, note_message, vec![]));
+    d
+}
+
+#[test]
+fn default_policy() {
+    let policy = LintPolicy::new();
+    assert_eq!(policy.apply(&warning(Some("W000"))), Some(warning(Some("W000"))));
+}
+
+#[test]
+fn by_code() {
+    let policy = LintPolicy::new()
+        .set("W000", LintLevel::Allow)
+        .set("W012", LintLevel::Deny)
+        .set("W013", LintLevel::Forbid);
+    assert_eq!(policy.apply(&warning(Some("W000"))), None);
+    assert_eq!(policy.apply(&warning(Some("W012"))),
+               Some(denied(Some("W012"), "requested on the command line with `-D W012`")));
+    assert_eq!(policy.apply(&warning(Some("W013"))),
+               Some(denied(Some("W013"), "requested on the command line with `-F W013`")));
+    assert_eq!(policy.apply(&warning(Some("W014"))), Some(warning(Some("W014"))));
+}
+
+#[test]
+fn warnings_group() {
+    let policy = LintPolicy::new()
+        .set("W000", LintLevel::Allow)
+        .set("W001", LintLevel::Warn)
+        .set("warnings", LintLevel::Deny);
+    assert_eq!(policy.apply(&warning(Some("W000"))), None);
+    assert_eq!(policy.apply(&warning(Some("W001"))),
+               Some(denied(Some("W001"), "`-D W001` implied by `-D warnings`")));
+    assert_eq!(policy.apply(&warning(Some("W012"))),
+               Some(denied(Some("W012"), "`-D W012` implied by `-D warnings`")));
+    assert_eq!(policy.apply(&warning(None)),
+               Some(denied(None, "requested on the command line with `-D warnings`")));
+
+    let policy = LintPolicy::new().set("warnings", LintLevel::Allow);
+    assert_eq!(policy.apply(&warning(Some("W012"))), None);
+}
+
+#[test]
+fn forbid_is_final() {
+    let policy = LintPolicy::new()
+        .set("W012", LintLevel::Forbid)
+        .set("W012", LintLevel::Allow)
+        .set("W013", LintLevel::Deny)
+        .set("W013", LintLevel::Allow);
+    assert_eq!(policy.get("W012"), Some(LintLevel::Forbid));
+    assert_eq!(policy.get("W013"), Some(LintLevel::Allow));
+}
+
+#[test]
+fn forbid_warnings() {
+    let policy = LintPolicy::new()
+        .set("W000", LintLevel::Allow)
+        .set("warnings", LintLevel::Forbid)
+        .set("W001", LintLevel::Allow)
+        .set("W002", LintLevel::Warn)
+        .set("W003", LintLevel::Deny)
+        .set("warnings", LintLevel::Allow);
+    assert_eq!(policy.apply(&warning(Some("W000"))),
+               Some(denied(Some("W000"), "`-F W000` implied by `-F warnings`")));
+    assert_eq!(policy.apply(&warning(Some("W001"))),
+               Some(denied(Some("W001"), "`-F W001` implied by `-F warnings`")));
+    assert_eq!(policy.apply(&warning(Some("W002"))),
+               Some(denied(Some("W002"), "`-F W002` implied by `-F warnings`")));
+    assert_eq!(policy.apply(&warning(Some("W003"))),
+               Some(denied(Some("W003"), "requested on the command line with `-D W003`")));
+}
+
+#[test]
+fn errors_unchanged() {
+    let policy = LintPolicy::new().set("E000", LintLevel::Allow).set("warnings", LintLevel::Allow);
+    let mut error = warning(Some("E000"));
+    error.level = Level::Error;
+    assert_eq!(policy.apply(&error), Some(error.clone()));
+}
+
+#[test]
+fn handler() {
+    let mut out = Vec::new();
+    {
+        let policy = LintPolicy::new()
+            .set("W000", LintLevel::Allow)
+            .set("W012", LintLevel::Deny);
+        let mut handler = Handler::new(Box::new(Emitter::vec(&mut out, None))).lint_policy(policy);
+        handler.emit(&[warning(Some("W000")), warning(Some("W012")), warning(Some("W013"))]);
+        assert_eq!(handler.error_count(), 1);
+        assert_eq!(handler.warning_count(), 1);
+        handler.finish();
+    }
+    assert_eq!(String::from_utf8(out).unwrap(), "\
+error[W012]: unused
+  |
+  = note: requested on the command line with `-D W012`
+warning[W013]: unused
+
+error: aborting due to previous error; 1 warning emitted
+
+");
+}
@@ -0,0 +1,104 @@
+extern crate codemap;
+extern crate codemap_diagnostic;
+
+mod common;
+
+use codemap_diagnostic::{ Diagnostic, Emitter, JsonEmitter, Level, Message, Style, SubDiagnostic,
+                          Suggestion };
+use common::{ code_map, diagnostic, styled, suggestion };
+
+fn mismatched() -> Diagnostic {
+    Diagnostic {
+        message: Message::new().normal("mismatched types in ").highlighted("`f`"),
+        children: vec![SubDiagnostic {
+            level: Level::Note,
+            message: Message::new()
+                .normal("expected ")
+                .highlighted("`u32`")
+                .normal(", found ")
+                .highlighted("`&str`"),
+            spans: vec![],
+        }],
+        ..diagnostic(Level::Error, "")
+    }
+}
+
+#[test]
+fn styled_parts() {
+    let rendered = Emitter::renderer(None).render(&[mismatched()]);
+    assert_eq!(rendered[0].1, vec![vec![
+        styled("error", Style::Level(Level::Error)),
+        styled(": mismatched types in ", Style::HeaderMsg),
+        styled("`f`", Style::Highlight),
+    ]]);
+    assert_eq!(rendered[2].1, vec![vec![
+        styled("  ", Style::NoStyle),
+        styled("= ", Style::LineNumber),
+        styled("note", Style::HeaderMsg),
+        styled(": expected ", Style::NoStyle),
+        styled("`u32`", Style::Highlight),
+        styled(", found ", Style::NoStyle),
+        styled("`&str`", Style::Highlight),
+    ]]);
+}
+
+#[test]
+fn plain_text() {
+    let mut out = Vec::new();
+    Emitter::vec(&mut out, None).emit(&[mismatched()]);
+    assert_eq!(String::from_utf8(out).unwrap(), "\
+error: mismatched types in `f`
+  |
+  = note: expected `u32`, found `&str`
+
+");
+
+    let mut out = Vec::new();
+    JsonEmitter::vec(&mut out, None).emit(&[mismatched()]);
+    let json = String::from_utf8(out).unwrap();
+    assert!(json.contains(r#""message":"mismatched types in `f`""#));
+    assert!(json.contains(r#""message":"expected `u32`, found `&str`""#));
+}
+
+#[test]
+fn suggestion_message() {
+    let (code_map, span) = code_map("let x: u32 = y;\n");
+    let d = Diagnostic {
+        suggestions: vec![Suggestion {
+            message: Message::new().normal("convert with ").highlighted("`u32::from`"),
+            ..suggestion(span.subspan(13, 14), "u32::from(y)")
+        }],
+        ..diagnostic(Level::Error, "mismatched types")
+    };
+    let rendered = Emitter::renderer(Some(&code_map)).render(std::slice::from_ref(&d));
+    assert_eq!(rendered[2].1[0], vec![
+        styled("help", Style::Level(Level::Help)),
+        styled(": convert with ", Style::HeaderMsg),
+        styled("`u32::from`", Style::Highlight),
+    ]);
+
+    let mut out = Vec::new();
+    JsonEmitter::vec(&mut out, Some(&code_map)).emit(&[d]);
+    let json = String::from_utf8(out).unwrap();
+    assert!(json.contains(r#""message":"convert with `u32::from`""#));
+}
+
+#[test]
+fn backticks() {
+    let d = diagnostic(Level::Warning, "unused variable `x` in `main");
+    let rendered = Emitter::renderer(None)
+        .highlight_backticks(true)
+        .render(std::slice::from_ref(&d));
+    assert_eq!(rendered[0].1, vec![vec![
+        styled("warning", Style::Level(Level::Warning)),
+        styled(": unused variable ", Style::HeaderMsg),
+        styled("`x`", Style::Highlight),
+        styled(" in `main", Style::HeaderMsg),
+    ]]);
+
+    let rendered = Emitter::renderer(None).render(&[d]);
+    assert_eq!(rendered[0].1, vec![vec![
+        styled("warning", Style::Level(Level::Warning)),
+        styled(": unused variable `x` in `main", Style::HeaderMsg),
+    ]]);
+}
@@ -0,0 +1,94 @@
+extern crate codemap;
+extern crate codemap_diagnostic;
+
+mod common;
+
+use codemap_diagnostic::{ Diagnostic, Level, MultilineFold };
+use common::{ code_map, diagnostic, emit, primary };
+const SOURCE: &str = "\
+fn main() {
+    let a = 1;
+    let b = 2;
+    let c = 3;
+    let d = 4;
+    let e = 5;
+    let f = 6;
+}
+";
+
+fn render(fold: MultilineFold) -> String {
+    let (code_map, file_span) = code_map(SOURCE);
+    let d = Diagnostic {
+        spans: vec![primary(file_span.subspan(0, SOURCE.len() as u64 - 1), Some("function"))],
+        ..diagnostic(Level::Error, "test")
+    };
+    emit(Some(&code_map), &[d], |e| e.multiline_fold(fold))
+}
+
+#[test]
+fn default() {
+    assert_eq!(render(MultilineFold::default()), "\
+error: test
+ --> test.rs:1:1
+  |
+1 | / fn main() {
+2 | |     let a = 1;
+3 | |     let b = 2;
+4 | |     let c = 3;
+... |
+7 | |     let f = 6;
+8 | | }
+  | |_^ function
+
+");
+}
+
+#[test]
+fn full() {
+    assert_eq!(render(MultilineFold::Full), "\
+error: test
+ --> test.rs:1:1
+  |
+1 | / fn main() {
+2 | |     let a = 1;
+3 | |     let b = 2;
+4 | |     let c = 3;
+5 | |     let d = 4;
+6 | |     let e = 5;
+7 | |     let f = 6;
+8 | | }
+  | |_^ function
+
+");
+}
+
+#[test]
+fn head_tail() {
+    assert_eq!(render(MultilineFold::HeadTail { head: 2, tail: 3 }), "\
+error: test
+ --> test.rs:1:1
+  |
+1 | / fn main() {
+2 | |     let a = 1;
+... |
+6 | |     let e = 5;
+7 | |     let f = 6;
+8 | | }
+  | |_^ function
+
+");
+}
+
+#[test]
+fn endpoints() {
+    assert_eq!(render(MultilineFold::Endpoints), "\
+error: test
+ --> test.rs:1:1
+  |
+1 | / fn main() {
+... |
+8 | | }
+  | |_^ function
+
+");
+}
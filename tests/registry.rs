@@ -0,0 +1,83 @@
+extern crate codemap;
+extern crate codemap_diagnostic;
+
+mod common;
+
+use codemap_diagnostic::{ Diagnostic, Emitter, Level, Registry, Style };
+use common::{ diagnostic, styled };
+
+const C000: &str = "\
+# Unresolved name
+
+A name was used that is not in scope, such as `foo` here:
+
+```
+let x = foo;
+```
+";
+
+fn with_code(level: Level, code: &str) -> Diagnostic {
+    Diagnostic { code: Some(code.to_owned()), ..diagnostic(level, "test") }
+}
+
+#[test]
+fn render_explanation() {
+    let registry = Registry::new().add("C000", C000);
+    assert_eq!(registry.render_explanation("C001"), None);
+    assert_eq!(registry.render_explanation("C000").unwrap(), vec![
+        vec![styled("Unresolved name", Style::Heading)],
+        vec![],
+        vec![
+            styled("A name was used that is not in scope, such as ", Style::NoStyle),
+            styled("`foo`", Style::Code),
+            styled(" here:", Style::NoStyle),
+        ],
+        vec![],
+        vec![styled("    let x = foo;", Style::Code)],
+    ]);
+}
+
+#[test]
+fn emit_explanation() {
+    let registry = Registry::new().add("C000", C000);
+    let mut out = Vec::new();
+    {
+        let mut emitter = Emitter::vec(&mut out, None);
+        assert!(emitter.emit_explanation(&registry, "C000"));
+        assert!(!emitter.emit_explanation(&registry, "C001"));
+    }
+    assert_eq!(String::from_utf8(out).unwrap(), "\
+Unresolved name
+
+A name was used that is not in scope, such as `foo` here:
+
+    let x = foo;
+");
+}
+
+#[test]
+fn footer() {
+    let registry = Registry::new().add("C000", C000).add("W000", "Unused.");
+    let mut out = Vec::new();
+    {
+        let mut emitter = Emitter::vec(&mut out, None).explain_footer(&registry, "tool --explain");
+        emitter.emit(&[
+            with_code(Level::Error, "C000"),
+            with_code(Level::Error, "C001"),
+            with_code(Level::Error, "C000"),
+        ]);
+        emitter.emit(&[with_code(Level::Warning, "W000"), with_code(Level::Error, "C000")]);
+    }
+    assert_eq!(String::from_utf8(out).unwrap(), "\
+error[C000]: test
+error[C001]: test
+error[C000]: test
+for more information about this error, try `tool --explain C000`
+
+warning[W000]: test
+error[C000]: test
+for more information about this warning, try `tool --explain W000`
+for more information about this error, try `tool --explain C000`
+
+");
+}
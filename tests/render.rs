@@ -0,0 +1,32 @@
+extern crate codemap;
+extern crate codemap_diagnostic;
+
+mod common;
+
+use codemap_diagnostic::{ Diagnostic, Emitter, Level, Style };
+use common::{ code_map, diagnostic, emit, primary };
+
+#[test]
+fn render_matches_emit() {
+    let (code_map, file) = code_map("let x = 1;\n");
+    let diagnostics = [Diagnostic {
+        spans: vec![primary(file.subspan(4, 5), Some("unused"))],
+        ..diagnostic(Level::Warning, "unused variable")
+    }];
+
+    let blocks = Emitter::renderer(Some(&code_map)).render(&diagnostics);
+    assert_eq!(blocks.len(), 1);
+    assert_eq!(blocks[0].0, Level::Warning);
+
+    let mut text = String::new();
+    for line in &blocks[0].1 {
+        for part in line {
+            text.push_str(&part.text);
+        }
+        text.push('\n');
+    }
+    assert_eq!(text + "\n", emit(Some(&code_map), &diagnostics, |e| e));
+
+    let underline = blocks[0].1.iter().flatten().find(|part| part.style == Style::UnderlinePrimary);
+    assert_eq!(underline.map(|part| &part.text[..]), Some("^"));
+}
@@ -0,0 +1,160 @@
+extern crate codemap;
+extern crate codemap_diagnostic;
+
+mod common;
+
+use std::io::{ self, Write };
+use codemap::CodeMap;
+use codemap_diagnostic::{ Diagnostic, Level, SarifEmitter };
+use common::{ code_map, diagnostic, primary, secondary };
+
+fn finish(code_map: Option<&CodeMap>, groups: &[&[Diagnostic]]) -> String {
+    let mut out = Vec::new();
+    {
+        let mut emitter = SarifEmitter::vec(&mut out, "tool", code_map);
+        for group in groups {
+            emitter.emit(group);
+        }
+        emitter.finish();
+    }
+    String::from_utf8(out).unwrap()
+}
+
+fn with_code(level: Level, code: &str) -> Diagnostic {
+    Diagnostic { code: Some(code.to_owned()), ..diagnostic(level, "test") }
+}
+
+#[test]
+fn levels() {
+    let diagnostics = [
+        diagnostic(Level::Bug, "bug"),
+        diagnostic(Level::Error, "error"),
+        diagnostic(Level::Warning, "warning"),
+        diagnostic(Level::Note, "note"),
+        diagnostic(Level::Help, "help"),
+    ];
+    assert_eq!(finish(None, &[&diagnostics]), concat!(
+        r#"{"$schema":"https://json.schemastore.org/sarif-2.1.0.json","version":"2.1.0""#,
+        r#","runs":[{"tool":{"driver":{"name":"tool","rules":[]}}"#,
+        r#","columnKind":"unicodeCodePoints","results":[{"level":"error","message":{"text":"bug"}"#,
+        r#","locations":[],"relatedLocations":[]},{"level":"error","message":{"text":"error"}"#,
+        r#","locations":[],"relatedLocations":[]},{"level":"warning","message":{"text":"warning"}"#,
+        r#","locations":[],"relatedLocations":[]},{"level":"note","message":{"text":"note"}"#,
+        r#","locations":[],"relatedLocations":[]},{"level":"note","message":{"text":"help"}"#,
+        r#","locations":[],"relatedLocations":[]}]}]}"#,
+        "\n"));
+}
+
+#[test]
+fn rules() {
+    let first = [with_code(Level::Error, "E1"), with_code(Level::Warning, "W1")];
+    let second = [with_code(Level::Error, "E1"), diagnostic(Level::Error, "no code")];
+    assert_eq!(finish(None, &[&first, &second]), concat!(
+        r#"{"$schema":"https://json.schemastore.org/sarif-2.1.0.json","version":"2.1.0""#,
+        r#","runs":[{"tool":{"driver":{"name":"tool","rules":[{"id":"E1"},{"id":"W1"}]}}"#,
+        r#","columnKind":"unicodeCodePoints","results":[{"ruleId":"E1","level":"error""#,
+        r#","message":{"text":"test"},"locations":[],"relatedLocations":[]},{"ruleId":"W1""#,
+        r#","level":"warning","message":{"text":"test"},"locations":[]"#,
+        r#","relatedLocations":[]},{"ruleId":"E1","level":"error","message":{"text":"test"}"#,
+        r#","locations":[],"relatedLocations":[]},{"level":"error","message":{"text":"no code"}"#,
+        r#","locations":[],"relatedLocations":[]}]}]}"#,
+        "\n"));
+}
+
+#[test]
+fn locations() {
+    let (code_map, span) = code_map("let π = «ä»;\nlet y = π;\n");
+    let d = Diagnostic {
+        spans: vec![
+            secondary(span.subspan(4, 6), Some("defined here")),
+            primary(span.subspan(11, 13), Some("used here")),
+            secondary(span.subspan(25, 27), None),
+            primary(span.subspan(0, 20), None),
+        ],
+        ..diagnostic(Level::Error, "test")
+    };
+    assert_eq!(finish(Some(&code_map), &[&[d]]), concat!(
+        r#"{"$schema":"https://json.schemastore.org/sarif-2.1.0.json","version":"2.1.0""#,
+        r#","runs":[{"tool":{"driver":{"name":"tool","rules":[]}}"#,
+        r#","columnKind":"unicodeCodePoints","results":[{"level":"error""#,
+        r#","message":{"text":"test"}"#,
+        r#","locations":[{"physicalLocation":{"artifactLocation":{"uri":"test.rs"}"#,
+        r#","region":{"startLine":1,"startColumn":10,"endLine":1,"endColumn":11}}"#,
+        r#","message":{"text":"used here"}}"#,
+        r#",{"physicalLocation":{"artifactLocation":{"uri":"test.rs"}"#,
+        r#","region":{"startLine":1,"startColumn":1,"endLine":2,"endColumn":4}}}]"#,
+        r#","relatedLocations":[{"id":0,"physicalLocation":{"artifactLocation":{"uri":"test.rs"}"#,
+        r#","region":{"startLine":1,"startColumn":5,"endLine":1,"endColumn":6}}"#,
+        r#","message":{"text":"defined here"}},{"id":1"#,
+        r#","physicalLocation":{"artifactLocation":{"uri":"test.rs"},"region":{"startLine":2"#,
+        r#","startColumn":9,"endLine":2,"endColumn":10}}}]}]}]}"#,
+        "\n"));
+}
+
+#[test]
+fn finish_resets() {
+    let mut out = Vec::new();
+    {
+        let mut emitter = SarifEmitter::vec(&mut out, "tool", None);
+        emitter.emit(&[with_code(Level::Error, "E1")]);
+        emitter.finish();
+        emitter.finish();
+    }
+    let out = String::from_utf8(out).unwrap();
+    let logs: Vec<_> = out.lines().collect();
+    assert_eq!(logs.len(), 2);
+    assert!(logs[0].contains(r#""rules":[{"id":"E1"}]"#));
+    assert!(logs[0].contains(r#""ruleId":"E1""#));
+    assert_eq!(logs[1], concat!(
+        r#"{"$schema":"https://json.schemastore.org/sarif-2.1.0.json","version":"2.1.0""#,
+        r#","runs":[{"tool":{"driver":{"name":"tool","rules":[]}}"#,
+        r#","columnKind":"unicodeCodePoints","results":[]}]}"#));
+}
+
+#[test]
+fn file_uri() {
+    let mut code_map = CodeMap::new();
+    let span = code_map.add_file("src\\my file ä#1.rs".to_owned(), "x\n".to_owned()).span;
+    let d = Diagnostic {
+        spans: vec![primary(span.subspan(0, 1), None)],
+        ..diagnostic(Level::Error, "test")
+    };
+    let out = finish(Some(&code_map), &[&[d]]);
+    assert!(out.contains(r#""artifactLocation":{"uri":"src/my%20file%20%C3%A4%231.rs"}"#), "{}", out);
+}
+
+/// Fails the first write, and writes to `out` after that.
+struct FailFirst<'a> {
+    failed: bool,
+    out: &'a mut Vec<u8>,
+}
+
+impl<'a> Write for FailFirst<'a> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.failed {
+            self.failed = true;
+            return Err(io::Error::new(io::ErrorKind::BrokenPipe, "pipe closed"));
+        }
+        self.out.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn finish_keeps_log_after_error() {
+    let mut out = Vec::new();
+    {
+        let dst = FailFirst { failed: false, out: &mut out };
+        let mut emitter = SarifEmitter::new(Box::new(dst), "tool", None);
+        emitter.emit(&[with_code(Level::Error, "E1")]);
+        assert!(emitter.try_finish().is_err());
+        emitter.try_finish().unwrap();
+    }
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(out.lines().count(), 1);
+    assert!(out.contains(r#""rules":[{"id":"E1"}]"#));
+    assert!(out.contains(r#""ruleId":"E1""#));
+}
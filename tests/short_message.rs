@@ -0,0 +1,60 @@
+extern crate codemap;
+extern crate codemap_diagnostic;
+
+mod common;
+
+use codemap_diagnostic::{ Diagnostic, Emitter, Level };
+use common::{ child, code_map, diagnostic, emit, primary, secondary };
+
+#[test]
+fn with_code_map() {
+    let (code_map, span) = code_map("fn f() {\n    let x = y;\n}\n");
+    let d = Diagnostic {
+        code: Some("E0425".to_owned()),
+        spans: vec![
+            secondary(span.subspan(3, 4), Some("in this function")),
+            primary(span.subspan(21, 22), Some("not found")),
+            secondary(span.subspan(17, 18), None),
+        ],
+        children: vec![child(Level::Help, "children are not shown", vec![])],
+        ..diagnostic(Level::Error, "cannot find value `y`")
+    };
+    let warning = Diagnostic {
+        spans: vec![primary(span.subspan(17, 18), None)],
+        ..diagnostic(Level::Warning, "unused variable")
+    };
+    assert_eq!(emit(Some(&code_map), &[d, warning], |e| e.short_message(true)), "\
+test.rs:2:13: error[E0425]: cannot find value `y`
+test.rs:1:4: note: in this function
+test.rs:2:9: warning: unused variable
+");
+}
+
+#[test]
+fn without_code_map() {
+    let (_code_map, span) = code_map("let x = y;");
+    let d = Diagnostic {
+        code: Some("E0425".to_owned()),
+        spans: vec![primary(span.subspan(8, 9), None), secondary(span.subspan(4, 5), Some("x"))],
+        ..diagnostic(Level::Error, "cannot find value `y`")
+    };
+    let no_spans = diagnostic(Level::Note, "no spans");
+    assert_eq!(emit(None, &[d, no_spans], |e| e.short_message(true)), "\
+error[E0425]: cannot find value `y`
+note: no spans
+");
+}
+
+#[test]
+fn no_blank_line_between_groups() {
+    let mut out = Vec::new();
+    {
+        let mut emitter = Emitter::vec(&mut out, None).short_message(true);
+        emitter.emit(&[diagnostic(Level::Error, "first")]);
+        emitter.emit(&[diagnostic(Level::Error, "second")]);
+    }
+    assert_eq!(String::from_utf8(out).unwrap(), "\
+error: first
+error: second
+");
+}
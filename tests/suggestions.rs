@@ -0,0 +1,127 @@
+extern crate codemap;
+extern crate codemap_diagnostic;
+
+mod common;
+
+use codemap_diagnostic::{ Diagnostic, Level };
+use common::{ code_map, diagnostic, emit, suggestion };
+
+const SOURCE: &str = "\
+fn main() {
+    let x = 1;
+    println!(\"{}\", y);
+}
+";
+
+fn render(start: usize, end: usize, replacement: &str) -> String {
+    let (code_map, file_span) = code_map(SOURCE);
+    let d = Diagnostic {
+        suggestions: vec![suggestion(file_span.subspan(start as u64, end as u64), replacement)],
+        ..diagnostic(Level::Error, "test")
+    };
+    emit(Some(&code_map), &[d], |e| e)
+}
+
+fn offset(needle: &str) -> usize {
+    SOURCE.find(needle).unwrap()
+}
+
+#[test]
+fn insertion() {
+    let at = offset("x = 1");
+    assert_eq!(render(at, at, "mut "), "\
+error: test
+  |
+help: try
+  |
+2 |     let mut x = 1;
+  |         ++++
+
+");
+}
+
+#[test]
+fn replacement() {
+    let at = offset("y)");
+    assert_eq!(render(at, at + 1, "x"), "\
+error: test
+  |
+help: try
+  |
+3 |     println!(\"{}\", x);
+  |                    ~
+
+");
+}
+
+#[test]
+fn deletion() {
+    let at = offset("let x");
+    assert_eq!(render(at, at + 4, ""), "\
+error: test
+  |
+help: try
+  |
+2 |     let x = 1;
+  |     ----
+
+");
+}
+
+#[test]
+fn multiline_replacement() {
+    let at = offset("println");
+    assert_eq!(render(at, offset(";\n}"), "if x > 0 {\n        println!(\"{}\", x);\n    }"), "\
+error: test
+  |
+help: try
+  |
+3 |     if x > 0 {
+  |     ~~~~~~~~~~
+4 |         println!(\"{}\", x);
+  |         ~~~~~~~~~~~~~~~~~~
+5 |     };
+  |     ~
+
+");
+}
+
+#[test]
+fn whole_line_insertion() {
+    let at = offset("    println");
+    assert_eq!(render(at, at, "    let y = 2;\n"), "\
+error: test
+  |
+help: try
+  |
+3 |     let y = 2;
+  |     ++++++++++
+4 |     println!(\"{}\", y);
+
+");
+}
+
+#[test]
+fn too_many_lines() {
+    let at = offset("    println");
+    assert_eq!(render(at, at, "    a();\n    b();\n    c();\n    d();\n    e();\n    f();\n    g();\n"), "\
+error: test
+   |
+help: try
+   |
+3  |     a();
+   |     ++++
+4  |     b();
+   |     ++++
+5  |     c();
+   |     ++++
+6  |     d();
+   |     ++++
+7  |     e();
+   |     ++++
+8  |     f();
+   |     ++++
+...
+
+");
+}